@@ -0,0 +1,15 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use imp_eth2::utils::{generate_enr_corpus, peer_ids_from_enrs, peer_ids_from_enrs_parallel};
+
+fn bench_peer_ids(c: &mut Criterion) {
+    let enrs = generate_enr_corpus(1000, 42);
+    c.bench_function("peer_ids_from_enrs", |b| {
+        b.iter(|| peer_ids_from_enrs(black_box(&enrs)))
+    });
+    c.bench_function("peer_ids_from_enrs_parallel", |b| {
+        b.iter(|| peer_ids_from_enrs_parallel(black_box(&enrs)))
+    });
+}
+
+criterion_group!(benches, bench_peer_ids);
+criterion_main!(benches);
@@ -0,0 +1,178 @@
+use super::{enr_to_rlp_bytes, MAX_ENR_SIZE};
+#[cfg(not(feature = "local"))]
+use discv5::enr::{CombinedKey, Enr};
+#[cfg(feature = "local")]
+use discv5_local::enr::{CombinedKey, Enr};
+use std::io::{self, Read, Write};
+
+/// The format version written at the head of a crawl archive.
+pub const CRAWL_ARCHIVE_VERSION: u8 = 1;
+
+/// An ENR seen by a crawler, with the unix times in seconds it was first and last seen.
+#[derive(Debug, Clone)]
+pub struct TrackedEnr {
+    pub enr: Enr<CombinedKey>,
+    pub first_seen: u64,
+    pub last_seen: u64,
+}
+
+/// Writes the entries as a crawl archive.
+///
+/// The archive is the `CRAWL_ARCHIVE_VERSION` byte followed by one record per entry: the length
+/// of the RLP encoded ENR as a big endian `u32`, the RLP bytes, then `first_seen` and `last_seen`
+/// as big endian `u64`s.
+pub fn write_crawl_archive<W: Write>(writer: &mut W, entries: &[TrackedEnr]) -> io::Result<()> {
+    writer.write_all(&[CRAWL_ARCHIVE_VERSION])?;
+    for entry in entries {
+        let rlp_bytes = enr_to_rlp_bytes(&entry.enr);
+        writer.write_all(&(rlp_bytes.len() as u32).to_be_bytes())?;
+        writer.write_all(&rlp_bytes)?;
+        writer.write_all(&entry.first_seen.to_be_bytes())?;
+        writer.write_all(&entry.last_seen.to_be_bytes())?;
+    }
+    writer.flush()
+}
+
+/// Like `read_exact`, but running out of input is an `InvalidData` error, as it can only mean a
+/// truncated record.
+fn read_record_bytes<R: Read>(reader: &mut R, bytes: &mut [u8]) -> io::Result<()> {
+    reader.read_exact(bytes).map_err(|e| {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            io::Error::new(io::ErrorKind::InvalidData, "truncated crawl archive record")
+        } else {
+            e
+        }
+    })
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    read_record_bytes(reader, &mut bytes)?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+/// Reads the first byte of the next record, or `None` at a clean end of the archive.
+fn read_record_start<R: Read>(reader: &mut R) -> io::Result<Option<u8>> {
+    let mut byte = [0u8; 1];
+    loop {
+        match reader.read(&mut byte) {
+            Ok(0) => return Ok(None),
+            Ok(_) => return Ok(Some(byte[0])),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Reads a crawl archive written by `write_crawl_archive`.
+///
+/// Unknown versions, truncated records, ENR lengths above `MAX_ENR_SIZE` and undecodable ENRs are
+/// `InvalidData` errors. The length is checked before anything is allocated for the ENR.
+pub fn read_crawl_archive<R: Read>(reader: &mut R) -> io::Result<Vec<TrackedEnr>> {
+    let mut version = [0u8; 1];
+    reader.read_exact(&mut version)?;
+    if version[0] != CRAWL_ARCHIVE_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported crawl archive version {}", version[0]),
+        ));
+    }
+
+    let mut entries = vec![];
+    while let Some(first_length_byte) = read_record_start(reader)? {
+        let mut length_bytes = [first_length_byte, 0, 0, 0];
+        read_record_bytes(reader, &mut length_bytes[1..])?;
+        let length = u32::from_be_bytes(length_bytes) as usize;
+        if length > MAX_ENR_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "crawl archive ENR length {} exceeds {}",
+                    length, MAX_ENR_SIZE
+                ),
+            ));
+        }
+        let mut rlp_bytes = vec![0u8; length];
+        read_record_bytes(reader, &mut rlp_bytes)?;
+        let enr = rlp::decode::<Enr<CombinedKey>>(&rlp_bytes)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", e)))?;
+        entries.push(TrackedEnr {
+            enr,
+            first_seen: read_u64(reader)?,
+            last_seen: read_u64(reader)?,
+        });
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(not(feature = "local"))]
+    use discv5::enr::EnrBuilder;
+    #[cfg(feature = "local")]
+    use discv5_local::enr::EnrBuilder;
+
+    fn tracked_enrs(count: u64) -> Vec<TrackedEnr> {
+        (0..count)
+            .map(|i| TrackedEnr {
+                enr: EnrBuilder::new("v4")
+                    .ip("10.0.0.1".parse().unwrap())
+                    .udp(9000)
+                    .build(&CombinedKey::generate_secp256k1())
+                    .unwrap(),
+                first_seen: 1_600_000_000 + i,
+                last_seen: 1_600_000_100 + i,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_crawl_archive_round_trip() {
+        let entries = tracked_enrs(3);
+        let mut archive = vec![];
+        write_crawl_archive(&mut archive, &entries).unwrap();
+
+        let read = read_crawl_archive(&mut archive.as_slice()).unwrap();
+        assert_eq!(read.len(), entries.len());
+        for (a, b) in read.iter().zip(entries.iter()) {
+            assert_eq!(a.enr.to_base64(), b.enr.to_base64());
+            assert_eq!((a.first_seen, a.last_seen), (b.first_seen, b.last_seen));
+        }
+        archive[0] = 0xff;
+        assert!(read_crawl_archive(&mut archive.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_crawl_archive_truncated() {
+        let mut archive = vec![];
+        write_crawl_archive(&mut archive, &tracked_enrs(2)).unwrap();
+        assert_eq!(read_crawl_archive(&mut &archive[..1]).unwrap().len(), 0);
+        let record_len = (archive.len() - 1) / 2;
+        assert_eq!(
+            read_crawl_archive(&mut &archive[..1 + record_len])
+                .unwrap()
+                .len(),
+            1
+        );
+        // cut inside the length prefix, the ENR and the timestamps of the second record
+        for cut in &[1, 3, 4, 10, record_len - 4] {
+            let err = read_crawl_archive(&mut &archive[..1 + record_len + cut]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn test_crawl_archive_oversized_length() {
+        let mut archive = vec![CRAWL_ARCHIVE_VERSION];
+        archive.extend_from_slice(&u32::MAX.to_be_bytes());
+        let err = read_crawl_archive(&mut archive.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let mut archive = vec![CRAWL_ARCHIVE_VERSION];
+        archive.extend_from_slice(&(MAX_ENR_SIZE as u32 + 1).to_be_bytes());
+        archive.extend(vec![0u8; MAX_ENR_SIZE + 17]);
+        let err = read_crawl_archive(&mut archive.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
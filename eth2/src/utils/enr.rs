@@ -0,0 +1,1408 @@
+use super::{attnets_length_valid, get_fork_id_from_enr, get_syncnets_from_enr, EnrExt};
+use crate::ssz::types::BitVector;
+use crate::ssz::{Decode, Encode};
+use crate::types::{ChainSpec, EnrForkId, EthSpec};
+#[cfg(not(feature = "local"))]
+use discv5::enr::{
+    CombinedKey, CombinedPublicKey, Enr, EnrBuilder, EnrError, EnrKey, EnrPublicKey, NodeId,
+};
+#[cfg(feature = "local")]
+use discv5_local::enr::{
+    CombinedKey, CombinedPublicKey, Enr, EnrBuilder, EnrError, EnrKey, EnrPublicKey, NodeId,
+};
+use libp2p_core::{multiaddr::Protocol, Multiaddr, PeerId};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// The maximum size in bytes of an RLP-encoded ENR, as defined by EIP-778.
+pub const MAX_ENR_SIZE: usize = 300;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EnrParseError {
+    /// The input is not valid hex.
+    InvalidHex,
+    /// The input could not be decoded into a valid record.
+    InvalidRecord(String),
+    /// The input is longer than the allowed maximum and was not decoded.
+    TooLong(usize),
+}
+
+/// The eth2 specific fields advertised in an ENR.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Eth2EnrInfo {
+    pub fork_id: Option<EnrForkId>,
+    pub attnets: Vec<u64>,
+    pub syncnets: Vec<u64>,
+}
+
+/// A defect found while validating an ENR.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EnrValidationIssue {
+    /// Neither the `ip` nor the `ip6` field holds a publicly routable address.
+    NoRoutableIp,
+    /// Neither the `tcp` nor the `tcp6` field is set.
+    NoTcpPort,
+    /// The record signature does not verify against its public key.
+    InvalidSignature,
+    /// The `eth2` field is not set.
+    MissingEth2Field,
+    /// The `eth2` field could not be decoded as an `EnrForkId`.
+    MalformedEth2Field,
+    /// The identity scheme is not `v4`, so no node id or peer id can be derived.
+    UnsupportedIdentityScheme,
+    /// The RLP encoded record is larger than `MAX_ENR_SIZE`, holding the encoded size.
+    TooLarge(usize),
+}
+
+pub fn is_routable_ipv4(ip: &Ipv4Addr) -> bool {
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast())
+}
+
+pub fn is_routable_ipv6(ip: &Ipv6Addr) -> bool {
+    let first_segment = ip.segments()[0];
+    // fc00::/7 is unique local and fe80::/10 is link local
+    let unique_local = (first_segment & 0xfe00) == 0xfc00;
+    let link_local = (first_segment & 0xffc0) == 0xfe80;
+    !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() || unique_local || link_local)
+}
+
+/// Returns true if either the `ip` or `ip6` field holds a publicly routable address.
+pub fn enr_has_routable_ip(enr: &Enr<CombinedKey>) -> bool {
+    enr.ip().map_or(false, |ip| is_routable_ipv4(&ip))
+        || enr.ip6().map_or(false, |ip6| is_routable_ipv6(&ip6))
+}
+
+pub fn enr_has_tcp_port(enr: &Enr<CombinedKey>) -> bool {
+    enr.tcp().is_some() || enr.tcp6().is_some()
+}
+
+/// Checks that each IP family with an address also has ports, and that each port has an address.
+///
+/// Returns a description of every inconsistency found.
+pub fn enr_port_consistency(enr: &Enr<CombinedKey>) -> Result<(), Vec<String>> {
+    let has_ip = enr.enr_ipv4().is_some();
+    let has_ip6 = enr.enr_ipv6().is_some();
+    let pairs = [
+        ("ip", has_ip, "tcp", enr.tcp().is_some()),
+        ("ip", has_ip, "udp", enr.udp().is_some()),
+        ("ip6", has_ip6, "tcp6", enr.tcp6().is_some()),
+        ("ip6", has_ip6, "udp6", enr.udp6().is_some()),
+    ];
+    let mut inconsistencies = vec![];
+    for (ip_field, has_ip, port_field, has_port) in pairs.iter() {
+        if *has_ip && !*has_port {
+            inconsistencies.push(format!("has {} but no {} port", ip_field, port_field));
+        } else if !*has_ip && *has_port {
+            inconsistencies.push(format!("has {} port but no {}", port_field, ip_field));
+        }
+    }
+    if inconsistencies.is_empty() {
+        Ok(())
+    } else {
+        Err(inconsistencies)
+    }
+}
+
+pub fn verify_enr_signature(enr: &Enr<CombinedKey>) -> bool {
+    enr.verify()
+}
+
+pub fn validate_eth2_field(enr: &Enr<CombinedKey>) -> Result<(), EnrValidationIssue> {
+    match enr.get("eth2") {
+        None => Err(EnrValidationIssue::MissingEth2Field),
+        Some(_) => match get_fork_id_from_enr(enr) {
+            Some(_) => Ok(()),
+            None => Err(EnrValidationIssue::MalformedEth2Field),
+        },
+    }
+}
+
+/// Returns true if the ENR has an `eth2` field, marking it as a consensus layer record.
+pub fn is_eth2_enr(enr: &Enr<CombinedKey>) -> bool {
+    enr.get("eth2").is_some()
+}
+
+/// The raw, SSZ encoded `eth2` field.
+pub fn get_eth2_field_bytes(enr: &Enr<CombinedKey>) -> Option<Vec<u8>> {
+    enr.get("eth2").map(|bytes| bytes.to_vec())
+}
+
+/// A hash of the whole `eth2` field, for grouping ENRs that advertise identical fork ids.
+///
+/// Unlike the fork digest alone this separates peers with different next fork schedules. The
+/// hash is stable within a process but should not be persisted.
+pub fn eth2_field_hash(enr: &Enr<CombinedKey>) -> Option<u64> {
+    let bytes = get_eth2_field_bytes(enr)?;
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+/// Returns true if the raw `eth2` field is exactly the 16 bytes of an SSZ `EnrForkId`: the 4 byte
+/// fork digest, the 4 byte next fork version and the 8 byte next fork epoch.
+///
+/// Longer fields may still decode but carry vendor-appended data.
+pub fn eth2_field_is_canonical_length(enr: &Enr<CombinedKey>) -> bool {
+    get_eth2_field_bytes(enr).map_or(false, |bytes| bytes.len() == 16)
+}
+
+/// Splits ENRs into `(consensus, execution)` layer records by their `eth2` or `eth`/`les` fields.
+///
+/// Records with neither are dropped.
+pub fn partition_cl_el(
+    enrs: Vec<Enr<CombinedKey>>,
+) -> (Vec<Enr<CombinedKey>>, Vec<Enr<CombinedKey>>) {
+    let mut consensus = vec![];
+    let mut execution = vec![];
+    for enr in enrs {
+        if is_eth2_enr(&enr) {
+            consensus.push(enr);
+        } else if enr.get("eth").is_some() || enr.get("les").is_some() {
+            execution.push(enr);
+        }
+    }
+    (consensus, execution)
+}
+
+/// Checks that every key in `required` is set, returning the missing keys in the order given.
+pub fn enr_has_required_fields(
+    enr: &Enr<CombinedKey>,
+    required: &[&str],
+) -> Result<(), Vec<String>> {
+    let missing: Vec<String> = required
+        .iter()
+        .filter(|key| enr.get(key).is_none())
+        .map(|key| key.to_string())
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(missing)
+    }
+}
+
+/// Pre-flight check for a local ENR before it is advertised.
+///
+/// All checks are run and every issue found is returned.
+pub fn validate_self_enr(enr: &Enr<CombinedKey>) -> Result<(), Vec<EnrValidationIssue>> {
+    let mut issues = vec![];
+    if !enr_has_routable_ip(enr) {
+        issues.push(EnrValidationIssue::NoRoutableIp);
+    }
+    if !enr_has_tcp_port(enr) {
+        issues.push(EnrValidationIssue::NoTcpPort);
+    }
+    if !verify_enr_signature(enr) {
+        issues.push(EnrValidationIssue::InvalidSignature);
+    }
+    if let Err(issue) = validate_eth2_field(enr) {
+        issues.push(issue);
+    }
+
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(issues)
+    }
+}
+
+/// Checks a received ENR before it is inserted into a store.
+///
+/// Runs the signature, identity scheme, eth2 field and size checks and returns every issue found.
+pub fn validate_enr_for_store(enr: &Enr<CombinedKey>) -> Result<(), Vec<EnrValidationIssue>> {
+    let mut issues = vec![];
+    if !verify_enr_signature(enr) {
+        issues.push(EnrValidationIssue::InvalidSignature);
+    }
+    if !enr_scheme_supported(enr) {
+        issues.push(EnrValidationIssue::UnsupportedIdentityScheme);
+    }
+    if let Err(issue) = validate_eth2_field(enr) {
+        issues.push(issue);
+    }
+    let size = enr_encoded_size(enr);
+    if size > MAX_ENR_SIZE {
+        issues.push(EnrValidationIssue::TooLarge(size));
+    }
+
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(issues)
+    }
+}
+
+/// Counts of how many entries in a corpus failed each decoding step.
+///
+/// A record can fail both the eth2 and attnets checks, so those counts may overlap.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DecodeStats {
+    pub total: usize,
+    pub parse_failures: usize,
+    pub missing_eth2: usize,
+    pub malformed_eth2: usize,
+    pub bad_attnets: usize,
+    /// Entries that parsed with a valid `eth2` field and an `attnets` field of the right length.
+    pub valid: usize,
+}
+
+/// Decodes every entry of a crawl dump and tallies the failures.
+pub fn corpus_decode_stats<E: EthSpec>(entries: &[String]) -> DecodeStats {
+    let mut stats = DecodeStats::default();
+    for entry in entries {
+        stats.total += 1;
+        let enr = match parse_enr(entry.trim()) {
+            Ok(enr) => enr,
+            Err(_) => {
+                stats.parse_failures += 1;
+                continue;
+            }
+        };
+        let eth2_result = validate_eth2_field(&enr);
+        match eth2_result {
+            Err(EnrValidationIssue::MissingEth2Field) => stats.missing_eth2 += 1,
+            Err(_) => stats.malformed_eth2 += 1,
+            Ok(()) => {}
+        }
+        let attnets_valid = attnets_length_valid::<E, _>(&enr);
+        if !attnets_valid {
+            stats.bad_attnets += 1;
+        }
+        if eth2_result.is_ok() && attnets_valid {
+            stats.valid += 1;
+        }
+    }
+    stats
+}
+
+/// The discv5 log2 distance between two node ids, or `None` if they are equal.
+pub fn log_distance(a: &NodeId, b: &NodeId) -> Option<u64> {
+    let a = a.raw();
+    let b = b.raw();
+    let leading_zeros: u32 = a
+        .iter()
+        .zip(b.iter())
+        .map(|(x, y)| x ^ y)
+        .position(|byte| byte != 0)
+        .map(|i| i as u32 * 8 + (a[i] ^ b[i]).leading_zeros())?;
+    Some(256 - leading_zeros as u64)
+}
+
+/// Sorts the ENRs in place by log distance from `reference`, nearest first.
+///
+/// Ties are broken by node id bytes so the ordering is deterministic.
+pub fn sort_enrs_by_distance(reference: &NodeId, enrs: &mut Vec<Enr<CombinedKey>>) {
+    enrs.sort_by_key(|enr| {
+        let node_id = enr.node_id();
+        (log_distance(reference, &node_id), node_id.raw())
+    });
+}
+
+/// A GraphViz `dot` graph with an edge from `reference` to each ENR, weighted by log distance.
+///
+/// Peers are labeled by `enr_fingerprint` and listed nearest first, ties broken by node id, so
+/// the same input always gives the same output. An ENR for the reference node itself is skipped.
+pub fn enrs_to_distance_dot(reference: &NodeId, enrs: &[Enr<CombinedKey>]) -> String {
+    let mut sorted = enrs.to_vec();
+    sort_enrs_by_distance(reference, &mut sorted);
+
+    let reference_id = hex::encode(reference.raw());
+    let mut dot = String::from("graph distances {\n");
+    dot.push_str(&format!("    \"{}\" [label=\"self\"];\n", reference_id));
+    for enr in &sorted {
+        let node_id = enr.node_id();
+        let distance = match log_distance(reference, &node_id) {
+            Some(distance) => distance,
+            None => continue,
+        };
+        let node_id_hex = hex::encode(node_id.raw());
+        dot.push_str(&format!(
+            "    \"{}\" [label=\"{}\"];\n",
+            node_id_hex,
+            enr_fingerprint(enr)
+        ));
+        dot.push_str(&format!(
+            "    \"{}\" -- \"{}\" [weight={}, label=\"{}\"];\n",
+            reference_id, node_id_hex, distance, distance
+        ));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Converts an ENR from the `discv5` crate into the `discv5_local` type by re-parsing its base64
+/// form.
+#[cfg(all(feature = "discv5", feature = "discv5_local"))]
+pub fn enr_to_local(
+    enr: &discv5::enr::Enr<discv5::enr::CombinedKey>,
+) -> Result<discv5_local::enr::Enr<discv5_local::enr::CombinedKey>, String> {
+    enr.to_base64().parse()
+}
+
+/// Converts an ENR from the `discv5_local` crate into the `discv5` type by re-parsing its base64
+/// form.
+#[cfg(all(feature = "discv5", feature = "discv5_local"))]
+pub fn enr_from_local(
+    enr: &discv5_local::enr::Enr<discv5_local::enr::CombinedKey>,
+) -> Result<discv5::enr::Enr<discv5::enr::CombinedKey>, String> {
+    enr.to_base64().parse()
+}
+
+/// The identity scheme named by the ENR's `id` field, normally `v4`.
+pub fn enr_identity_scheme(enr: &Enr<CombinedKey>) -> Option<String> {
+    enr.get("id")
+        .and_then(|id| String::from_utf8(id.to_vec()).ok())
+}
+
+/// Returns true only for the `v4` identity scheme, the only one node ids can be derived for.
+pub fn enr_scheme_supported(enr: &Enr<CombinedKey>) -> bool {
+    enr_identity_scheme(enr).as_deref() == Some("v4")
+}
+
+pub fn enrs_same_node(a: &Enr<CombinedKey>, b: &Enr<CombinedKey>) -> bool {
+    a.node_id() == b.node_id()
+}
+
+/// Returns true if the record's node id is `expected`, for checking records fetched by node id.
+pub fn enr_node_id_matches(enr: &Enr<CombinedKey>, expected: &NodeId) -> bool {
+    enr.node_id() == *expected
+}
+
+/// Returns true if `incoming` is a newer record for the same node as `stored`.
+pub fn enr_is_update(incoming: &Enr<CombinedKey>, stored: &Enr<CombinedKey>) -> bool {
+    enrs_same_node(incoming, stored) && incoming.seq() > stored.seq()
+}
+
+/// Checks that an ENR history is all for one node with strictly increasing sequence numbers.
+///
+/// Returns the index of the first entry that is for another node or does not increase the
+/// sequence number.
+pub fn validate_enr_history(enrs: &[Enr<CombinedKey>]) -> Result<(), usize> {
+    for (i, pair) in enrs.windows(2).enumerate() {
+        if !enrs_same_node(&pair[0], &pair[1]) || pair[1].seq() <= pair[0].seq() {
+            return Err(i + 1);
+        }
+    }
+    Ok(())
+}
+
+/// Removes ENRs for the same node, keeping the one with the highest sequence number.
+///
+/// Nodes are output in the order they first appear.
+pub fn dedup_enrs_keep_newest(enrs: Vec<Enr<CombinedKey>>) -> Vec<Enr<CombinedKey>> {
+    let mut deduped: Vec<Enr<CombinedKey>> = vec![];
+    for enr in enrs {
+        match deduped.iter().position(|x| enrs_same_node(x, &enr)) {
+            Some(i) if enr.seq() > deduped[i].seq() => deduped[i] = enr,
+            Some(_) => (),
+            None => deduped.push(enr),
+        }
+    }
+    deduped
+}
+
+/// The peer id and every multiaddr of the ENR, as libp2p peer routing takes them.
+///
+/// The addresses are empty for ENRs with no dialable transport.
+pub fn enr_to_peer_routing_entry(enr: &Enr<CombinedKey>) -> (PeerId, Vec<Multiaddr>) {
+    (enr.peer_id(), enr.all_multiaddrs())
+}
+
+/// The peer id of each ENR, in input order.
+pub fn peer_ids_from_enrs(enrs: &[Enr<CombinedKey>]) -> Vec<PeerId> {
+    enrs.iter().map(|enr| enr.peer_id()).collect()
+}
+
+/// Like `peer_ids_from_enrs`, decoding the public keys on the rayon thread pool.
+#[cfg(feature = "rayon")]
+pub fn peer_ids_from_enrs_parallel(enrs: &[Enr<CombinedKey>]) -> Vec<PeerId> {
+    enrs.par_iter().map(|enr| enr.peer_id()).collect()
+}
+
+/// Indexes ENRs by the peer id derived from their public key.
+///
+/// ENRs with the same peer id are the same node, and the one with the highest sequence number is
+/// kept. On equal sequence numbers the first one seen wins.
+pub fn index_enrs_by_peer_id(enrs: Vec<Enr<CombinedKey>>) -> HashMap<PeerId, Enr<CombinedKey>> {
+    let mut index: HashMap<PeerId, Enr<CombinedKey>> = HashMap::new();
+    for enr in enrs {
+        let peer_id = enr.peer_id();
+        match index.get(&peer_id) {
+            Some(stored) if stored.seq() >= enr.seq() => (),
+            _ => {
+                index.insert(peer_id, enr);
+            }
+        }
+    }
+    index
+}
+
+/// Accumulates discovered ENRs, keeping the newest record per node.
+#[derive(Default)]
+pub struct DiscoveryTracker {
+    enrs: HashMap<NodeId, Enr<CombinedKey>>,
+    total: usize,
+    stale: usize,
+}
+
+impl DiscoveryTracker {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Records a discovered ENR, replacing the stored one if its sequence number is higher.
+    pub fn record(&mut self, enr: &Enr<CombinedKey>) {
+        self.total += 1;
+        match self.enrs.get(&enr.node_id()) {
+            Some(stored) if stored.seq() >= enr.seq() => self.stale += 1,
+            _ => {
+                self.enrs.insert(enr.node_id(), enr.clone());
+            }
+        }
+    }
+
+    pub fn unique_node_count(&self) -> usize {
+        self.enrs.len()
+    }
+
+    /// The number of ENRs recorded, including duplicates.
+    pub fn total_count(&self) -> usize {
+        self.total
+    }
+
+    /// The number of recorded ENRs that were not newer than the one already stored for the node.
+    pub fn stale_count(&self) -> usize {
+        self.stale
+    }
+
+    pub fn enrs(&self) -> Vec<Enr<CombinedKey>> {
+        self.enrs.values().cloned().collect()
+    }
+}
+
+/// A bloom filter of node ids, for remembering which nodes a crawl has seen without keeping
+/// their ENRs.
+///
+/// `maybe_seen` never misses an inserted node but can report nodes that were never inserted.
+/// With `m` bits, `k` hashes and `n` inserted nodes the false positive rate is about
+/// `(1 - e^(-k * n / m))^k`, so around 10 bits per expected node with 7 hashes gives 1%.
+pub struct NodeIdBloom {
+    bits: Vec<u64>,
+    num_bits: u64,
+    num_hashes: u64,
+}
+
+impl NodeIdBloom {
+    /// A filter of `num_bits` bits, each node id setting `num_hashes` of them.
+    pub fn new(num_bits: u64, num_hashes: u64) -> Self {
+        let num_bits = num_bits.max(1);
+        Self {
+            bits: vec![0; ((num_bits + 63) / 64) as usize],
+            num_bits,
+            num_hashes: num_hashes.max(1),
+        }
+    }
+
+    /// The bit positions for a node id. Node ids are already uniformly random, so two halves of
+    /// it are combined by double hashing instead of hashing again. The step is forced odd so it is
+    /// never zero, which would put every position on the same bit.
+    fn positions(&self, node_id: &NodeId) -> Vec<u64> {
+        let raw = node_id.raw();
+        let mut h1 = [0u8; 8];
+        let mut h2 = [0u8; 8];
+        h1.copy_from_slice(&raw[..8]);
+        h2.copy_from_slice(&raw[8..16]);
+        let h1 = u64::from_le_bytes(h1);
+        let h2 = u64::from_le_bytes(h2) | 1;
+        (0..self.num_hashes)
+            .map(|i| h1.wrapping_add(i.wrapping_mul(h2)) % self.num_bits)
+            .collect()
+    }
+
+    pub fn insert(&mut self, enr: &Enr<CombinedKey>) {
+        for position in self.positions(&enr.node_id()) {
+            self.bits[(position / 64) as usize] |= 1 << (position % 64);
+        }
+    }
+
+    /// Returns false if the node was definitely never inserted.
+    pub fn maybe_seen(&self, enr: &Enr<CombinedKey>) -> bool {
+        self.positions(&enr.node_id())
+            .iter()
+            .all(|position| self.bits[(position / 64) as usize] & (1 << (position % 64)) != 0)
+    }
+}
+
+/// Parses an ENR from its `enr:` prefixed base64 form.
+pub fn parse_enr(s: &str) -> Result<Enr<CombinedKey>, EnrParseError> {
+    s.parse::<Enr<CombinedKey>>()
+        .map_err(EnrParseError::InvalidRecord)
+}
+
+/// The `enr:` prefixed base64 text form, the inverse of `parse_enr`.
+pub fn enr_to_base64(enr: &Enr<CombinedKey>) -> String {
+    enr.to_base64()
+}
+
+/// Returns `Ok(true)` if re-encoding the parsed ENR gives back exactly `s`.
+///
+/// `Ok(false)` means the input parsed but is not in canonical form.
+pub fn enr_base64_roundtrips(s: &str) -> Result<bool, EnrParseError> {
+    let enr = parse_enr(s)?;
+    Ok(enr_to_base64(&enr) == s)
+}
+
+/// Parses an ENR, rejecting inputs longer than `max_len` before any decoding is attempted.
+///
+/// Use this for ENRs from untrusted sources, with a bound like 1KB.
+pub fn parse_enr_bounded(s: &str, max_len: usize) -> Result<Enr<CombinedKey>, EnrParseError> {
+    if s.len() > max_len {
+        return Err(EnrParseError::TooLong(s.len()));
+    }
+    parse_enr(s)
+}
+
+/// Parses an ENR from the hex encoding of its RLP bytes, with or without a `0x` prefix.
+pub fn parse_enr_hex(s: &str) -> Result<Enr<CombinedKey>, EnrParseError> {
+    let s = s.trim();
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    let bytes = hex::decode(s).map_err(|_| EnrParseError::InvalidHex)?;
+    rlp::decode::<Enr<CombinedKey>>(&bytes)
+        .map_err(|e| EnrParseError::InvalidRecord(format!("{:?}", e)))
+}
+
+/// A short fingerprint of the node id, the first 8 hex characters, for logs and dashboards.
+///
+/// This is not collision free and should not be used as a unique key.
+pub fn enr_fingerprint(enr: &Enr<CombinedKey>) -> String {
+    hex::encode(&enr.node_id().raw()[..4])
+}
+
+/// The fields to set when rebuilding an ENR with `rebuild_enr`. Fields left as `None` are omitted.
+#[derive(Debug, Clone, Default)]
+pub struct EnrFields {
+    pub ip: Option<Ipv4Addr>,
+    pub ip6: Option<Ipv6Addr>,
+    pub tcp: Option<u16>,
+    pub udp: Option<u16>,
+    pub quic: Option<u16>,
+    pub eth2: Option<EnrForkId>,
+    /// The SSZ encoded `attnets` bitfield.
+    pub attnets: Option<Vec<u8>>,
+    /// The SSZ encoded `syncnets` bitfield.
+    pub syncnets: Option<Vec<u8>>,
+    /// The sequence number of the rebuilt record.
+    pub seq: u64,
+}
+
+/// Builds and signs an ENR from a persisted key and the given fields in one step.
+pub fn rebuild_enr(key: &CombinedKey, fields: EnrFields) -> Result<Enr<CombinedKey>, EnrError> {
+    let mut builder = EnrBuilder::new("v4");
+    builder.seq(fields.seq);
+    if let Some(ip) = fields.ip {
+        builder.ip(IpAddr::V4(ip));
+    }
+    if let Some(ip6) = fields.ip6 {
+        builder.ip(IpAddr::V6(ip6));
+    }
+    if let Some(tcp) = fields.tcp {
+        builder.tcp(tcp);
+    }
+    if let Some(udp) = fields.udp {
+        builder.udp(udp);
+    }
+    if let Some(quic) = fields.quic {
+        builder.add_value("quic".into(), quic.to_be_bytes().to_vec());
+    }
+    if let Some(enr_fork_id) = fields.eth2 {
+        builder.add_value("eth2".into(), enr_fork_id.as_ssz_bytes());
+    }
+    if let Some(attnets) = fields.attnets {
+        builder.add_value("attnets".into(), attnets);
+    }
+    if let Some(syncnets) = fields.syncnets {
+        builder.add_value("syncnets".into(), syncnets);
+    }
+    builder.build(key)
+}
+
+/// The TCP port to dial, preferring `tcp` over `tcp6`.
+pub fn enr_preferred_tcp(enr: &Enr<CombinedKey>) -> Option<u16> {
+    enr.tcp().or_else(|| enr.tcp6())
+}
+
+/// The RLP encoding of the record, as used on the wire by discv5.
+pub fn enr_to_rlp_bytes(enr: &Enr<CombinedKey>) -> Vec<u8> {
+    rlp::encode(enr)
+}
+
+/// The length in bytes of the RLP-encoded record.
+pub fn enr_encoded_size(enr: &Enr<CombinedKey>) -> usize {
+    rlp::encode(enr).len()
+}
+
+/// Returns true if the record is within `MAX_ENR_SIZE`, so peers will accept it.
+pub fn enr_fits_size_limit(enr: &Enr<CombinedKey>) -> bool {
+    enr_encoded_size(enr) <= MAX_ENR_SIZE
+}
+
+/// The `enr:<base64>` text of an EIP-1459 `enrtree` leaf record.
+///
+/// A single TXT character-string holds at most 255 bytes, while a maximum size ENR encodes to
+/// around 400 characters. Longer values have to be split into several strings within the same
+/// TXT record, which not every DNS provider supports.
+pub fn enr_to_dns_txt(enr: &Enr<CombinedKey>) -> String {
+    enr.to_base64()
+}
+
+/// Returns true if the ENR advertises a `quic` or `quic6` port.
+pub fn enr_supports_quic(enr: &Enr<CombinedKey>) -> bool {
+    enr.get("quic").is_some() || enr.get("quic6").is_some()
+}
+
+pub fn filter_quic_enrs(enrs: Vec<Enr<CombinedKey>>) -> Vec<Enr<CombinedKey>> {
+    enrs.into_iter().filter(enr_supports_quic).collect()
+}
+
+/// Keys the `EnrBuilder` derives from the signing key instead of taking them from a field map.
+const PUBLIC_KEY_FIELDS: [&str; 2] = ["secp256k1", "ed25519"];
+
+/// Every key-value pair of the record, values as the raw bytes stored in the record.
+pub fn enr_to_field_map(enr: &Enr<CombinedKey>) -> BTreeMap<String, Vec<u8>> {
+    enr.iter()
+        .map(|(key, value)| (String::from_utf8_lossy(key).into_owned(), value.to_vec()))
+        .collect()
+}
+
+/// A line per field text form of the ENR, for fixtures that are diffed in version control.
+///
+/// The `seq` line comes first, then a `key = 0xvalue` line per field sorted by key, then the
+/// derived `node_id` and `peer_id`.
+pub fn enr_to_text_dump(enr: &Enr<CombinedKey>) -> String {
+    let mut dump = format!("seq = {}\n", enr.seq());
+    for (key, value) in enr_to_field_map(enr) {
+        dump.push_str(&format!("{} = 0x{}\n", key, hex::encode(value)));
+    }
+    dump.push_str(&format!(
+        "node_id = 0x{}\n",
+        hex::encode(enr.node_id().raw())
+    ));
+    dump.push_str(&format!("peer_id = {}\n", enr.peer_id()));
+    dump
+}
+
+/// Builds and signs a record from a field map, as produced by `enr_to_field_map`.
+///
+/// The `id` key is mandatory and names the identity scheme (`v4`). The public key field is always
+/// derived from `key`, so any `secp256k1` or `ed25519` entry in the map is ignored. All other
+/// keys, including `ip`, `tcp` and `udp`, are optional and copied as is. The sequence number is
+/// not part of the map and starts over.
+pub fn enr_from_field_map(
+    map: BTreeMap<String, Vec<u8>>,
+    key: &CombinedKey,
+) -> Result<Enr<CombinedKey>, EnrError> {
+    let id = map
+        .get("id")
+        .map(|id| String::from_utf8_lossy(id).into_owned())
+        .ok_or_else(|| EnrError::InvalidRlpData("missing id field".to_string()))?;
+    let mut builder = EnrBuilder::new(id);
+    for (field, value) in map {
+        if field != "id" && !PUBLIC_KEY_FIELDS.contains(&field.as_str()) {
+            builder.add_value(field, value);
+        }
+    }
+    builder.build(key)
+}
+
+/// Decodes the `eth2`, `attnets` and `syncnets` fields in one pass.
+pub fn get_eth2_info_from_enr<E: EthSpec>(enr: &Enr<CombinedKey>, spec: &ChainSpec) -> Eth2EnrInfo {
+    let attnets = enr
+        .get("attnets")
+        .and_then(|bytes| BitVector::<E::SubnetBitfieldLength>::from_ssz_bytes(bytes).ok())
+        .map(|bitfield| {
+            (0..spec.attestation_subnet_count)
+                .filter(|i| bitfield.get(*i as usize).unwrap_or(false))
+                .collect()
+        })
+        .unwrap_or_default();
+    Eth2EnrInfo {
+        fork_id: get_fork_id_from_enr(enr),
+        attnets,
+        syncnets: get_syncnets_from_enr(enr),
+    }
+}
+
+/// The EIP-778 keys plus the keys defined by the eth2 networking spec.
+pub const STANDARD_ENR_FIELDS: [&str; 14] = [
+    "id",
+    "secp256k1",
+    "ed25519",
+    "ip",
+    "ip6",
+    "tcp",
+    "tcp6",
+    "udp",
+    "udp6",
+    "quic",
+    "quic6",
+    "eth2",
+    "attnets",
+    "syncnets",
+];
+
+/// Rebuilds the record with only the `STANDARD_ENR_FIELDS`, signed by `key`.
+///
+/// Re-signing produces a new signature and resets the sequence number, so the result is a
+/// different record from the input. The node id only stays the same if `key` is the key the
+/// input was signed with.
+pub fn strip_custom_fields(
+    enr: &Enr<CombinedKey>,
+    key: &CombinedKey,
+) -> Result<Enr<CombinedKey>, EnrError> {
+    let map = enr_to_field_map(enr)
+        .into_iter()
+        .filter(|(field, _)| STANDARD_ENR_FIELDS.contains(&field.as_str()))
+        .collect();
+    enr_from_field_map(map, key)
+}
+
+/// The RLP encoded size of the record `key` would sign from the field map, without signing it.
+fn field_map_encoded_size(map: &BTreeMap<String, Vec<u8>>, seq: u64, key: &CombinedKey) -> usize {
+    let public_key = key.public();
+    let mut fields: BTreeMap<String, Vec<u8>> = map
+        .iter()
+        .filter(|(field, _)| !PUBLIC_KEY_FIELDS.contains(&field.as_str()))
+        .map(|(field, value)| (field.clone(), value.clone()))
+        .collect();
+    fields.insert(public_key.enr_key(), public_key.encode());
+
+    let mut stream = rlp::RlpStream::new_list(fields.len() * 2 + 2);
+    // both identity schemes have 64 byte signatures
+    stream.append(&vec![0u8; 64]);
+    stream.append(&seq);
+    for (field, value) in &fields {
+        stream.append(field);
+        stream.append(value);
+    }
+    stream.out().len()
+}
+
+/// Drops optional fields until the record signed from the field map fits within `MAX_ENR_SIZE`,
+/// then signs it once with sequence number `seq`.
+///
+/// A record that has been built already can never exceed the limit, so this takes the fields to
+/// be signed, in the form of `enr_to_field_map`. The sizes are computed without signing. Fields
+/// are dropped a group at a time, stopping as soon as the record fits: first every field not in
+/// `STANDARD_ENR_FIELDS`, then `ip6`, `tcp6` and `udp6`, then `quic` and `quic6`. The `id` field
+/// is required, as for `enr_from_field_map`, and a record that is still too large after all of
+/// the drops is an error.
+pub fn fit_enr_to_limit(
+    mut map: BTreeMap<String, Vec<u8>>,
+    seq: u64,
+    key: &CombinedKey,
+) -> Result<Enr<CombinedKey>, EnrError> {
+    let is_custom = |field: &str| !STANDARD_ENR_FIELDS.contains(&field);
+    let is_ip6 = |field: &str| ["ip6", "tcp6", "udp6"].contains(&field);
+    let is_quic = |field: &str| ["quic", "quic6"].contains(&field);
+    let drop_groups: [&dyn Fn(&str) -> bool; 3] = [&is_custom, &is_ip6, &is_quic];
+    for should_drop in drop_groups.iter() {
+        if field_map_encoded_size(&map, seq, key) <= MAX_ENR_SIZE {
+            break;
+        }
+        map.retain(|field, _| !should_drop(field.as_str()));
+    }
+    if field_map_encoded_size(&map, seq, key) > MAX_ENR_SIZE {
+        return Err(EnrError::ExceedsMaxSize);
+    }
+
+    let id = map
+        .get("id")
+        .map(|id| String::from_utf8_lossy(id).into_owned())
+        .ok_or_else(|| EnrError::InvalidRlpData("missing id field".to_string()))?;
+    let mut builder = EnrBuilder::new(id);
+    builder.seq(seq);
+    for (field, value) in map {
+        if field != "id" && !PUBLIC_KEY_FIELDS.contains(&field.as_str()) {
+            builder.add_value(field, value);
+        }
+    }
+    builder.build(key)
+}
+
+pub fn peer_id_to_base58(peer_id: &PeerId) -> String {
+    peer_id.to_base58()
+}
+
+pub fn peer_id_from_base58(s: &str) -> Result<PeerId, &'static str> {
+    s.parse::<PeerId>()
+        .map_err(|_| "Could not decode the base58 peer id")
+}
+
+/// Decodes a port field stored as up to two big endian bytes, like `tcp` and `udp`.
+fn get_port_field(enr: &Enr<CombinedKey>, key: &str) -> Option<u16> {
+    let bytes = enr.get(key)?;
+    if bytes.is_empty() || bytes.len() > 2 {
+        return None;
+    }
+    Some(
+        bytes
+            .iter()
+            .fold(0u16, |port, byte| (port << 8) | *byte as u16),
+    )
+}
+
+pub fn enr_quic(enr: &Enr<CombinedKey>) -> Option<u16> {
+    get_port_field(enr, "quic")
+}
+
+pub fn enr_quic6(enr: &Enr<CombinedKey>) -> Option<u16> {
+    get_port_field(enr, "quic6")
+}
+
+/// Returns true if the ENR has a routable IP and a TCP or QUIC port to connect to.
+///
+/// This only says the record is well formed for dialing, not that the node is reachable.
+pub fn enr_is_dialable(enr: &Enr<CombinedKey>) -> bool {
+    enr_has_routable_ip(enr)
+        && (enr_has_tcp_port(enr) || enr_quic(enr).is_some() || enr_quic6(enr).is_some())
+}
+
+fn with_peer_id(multiaddr: Multiaddr, peer_id: &PeerId) -> Multiaddr {
+    multiaddr.with(Protocol::P2p(peer_id.clone().into()))
+}
+
+/// The `/ip/tcp/p2p` multiaddrs of the ENR, IPv4 before IPv6.
+pub fn tcp_multiaddrs(enr: &Enr<CombinedKey>) -> Vec<Multiaddr> {
+    let peer_id = enr.peer_id();
+    let mut multiaddrs = vec![];
+    if let (Some(ip), Some(tcp)) = (enr.ip(), enr.tcp()) {
+        let multiaddr = Multiaddr::from(IpAddr::V4(ip)).with(Protocol::Tcp(tcp));
+        multiaddrs.push(with_peer_id(multiaddr, &peer_id));
+    }
+    if let (Some(ip6), Some(tcp6)) = (enr.ip6(), enr.tcp6()) {
+        let multiaddr = Multiaddr::from(IpAddr::V6(ip6)).with(Protocol::Tcp(tcp6));
+        multiaddrs.push(with_peer_id(multiaddr, &peer_id));
+    }
+    multiaddrs
+}
+
+/// The `/ip/udp/quic/p2p` multiaddrs of the ENR, IPv4 before IPv6.
+pub fn quic_multiaddrs(enr: &Enr<CombinedKey>) -> Vec<Multiaddr> {
+    let peer_id = enr.peer_id();
+    let mut multiaddrs = vec![];
+    if let (Some(ip), Some(quic)) = (enr.ip(), enr_quic(enr)) {
+        let multiaddr = Multiaddr::from(IpAddr::V4(ip))
+            .with(Protocol::Udp(quic))
+            .with(Protocol::Quic);
+        multiaddrs.push(with_peer_id(multiaddr, &peer_id));
+    }
+    if let (Some(ip6), Some(quic6)) = (enr.ip6(), enr_quic6(enr)) {
+        let multiaddr = Multiaddr::from(IpAddr::V6(ip6))
+            .with(Protocol::Udp(quic6))
+            .with(Protocol::Quic);
+        multiaddrs.push(with_peer_id(multiaddr, &peer_id));
+    }
+    multiaddrs
+}
+
+/// Every dialable multiaddr of the ENR in the order they should be tried.
+///
+/// QUIC addresses come before TCP addresses if `prefer_quic` is set, and after them otherwise.
+/// Within each transport IPv4 comes before IPv6.
+pub fn dial_addresses(enr: &Enr<CombinedKey>, prefer_quic: bool) -> Vec<Multiaddr> {
+    let (mut first, second) = if prefer_quic {
+        (quic_multiaddrs(enr), tcp_multiaddrs(enr))
+    } else {
+        (tcp_multiaddrs(enr), quic_multiaddrs(enr))
+    };
+    first.extend(second);
+    first
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyType {
+    Secp256k1,
+    Ed25519,
+}
+
+/// The type of key the record is signed with.
+pub fn enr_key_type(enr: &Enr<CombinedKey>) -> KeyType {
+    match enr.public_key() {
+        CombinedPublicKey::Secp256k1(_) => KeyType::Secp256k1,
+        CombinedPublicKey::Ed25519(_) => KeyType::Ed25519,
+    }
+}
+
+/// Returns true if the record is signed with one of the `allowed` key types.
+pub fn enr_key_type_acceptable(enr: &Enr<CombinedKey>, allowed: &[KeyType]) -> bool {
+    allowed.contains(&enr_key_type(enr))
+}
+
+/// Returns true if the ENR advertises the given fork digest.
+pub fn enr_matches_fork_digest(enr: &Enr<CombinedKey>, fork_digest: [u8; 4]) -> bool {
+    get_fork_id_from_enr(enr).map_or(false, |enr_fork_id| enr_fork_id.fork_digest == fork_digest)
+}
+
+/// The consensus client a node runs, as far as it can be told from its ENR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClientKind {
+    Lighthouse,
+    Prysm,
+    Teku,
+    Nimbus,
+    Lodestar,
+    Grandine,
+    Unknown,
+}
+
+/// The client name from the ENR's EIP-7636 `client` field.
+///
+/// The field is an RLP list of name, version and an optional build, but a bare name is accepted
+/// as well.
+pub fn enr_client_name(enr: &Enr<CombinedKey>) -> Option<String> {
+    let bytes = enr.get("client")?;
+    let rlp = rlp::Rlp::new(bytes);
+    let name: Vec<u8> = if rlp.is_list() {
+        rlp.val_at(0).ok()?
+    } else {
+        bytes.to_vec()
+    };
+    String::from_utf8(name).ok()
+}
+
+/// Guesses the client from the ENR's `client` field.
+///
+/// ENRs have no other client specific fields, so records without the field are `Unknown`.
+pub fn guess_client_from_enr(enr: &Enr<CombinedKey>) -> ClientKind {
+    let name = match enr_client_name(enr) {
+        Some(name) => name.to_lowercase(),
+        None => return ClientKind::Unknown,
+    };
+    match name.as_str() {
+        "lighthouse" => ClientKind::Lighthouse,
+        "prysm" => ClientKind::Prysm,
+        "teku" => ClientKind::Teku,
+        "nimbus" => ClientKind::Nimbus,
+        "lodestar" => ClientKind::Lodestar,
+        "grandine" => ClientKind::Grandine,
+        _ => ClientKind::Unknown,
+    }
+}
+
+/// The discv5 message type byte of a NODES response.
+pub const NODES_MESSAGE_TYPE: u8 = 0x04;
+
+/// Decodes an RLP item header, returning whether it is a list, the header length and the payload
+/// length. The payload itself may be truncated. Lengths that do not fit a `usize` yield `None`.
+fn rlp_item_header(bytes: &[u8]) -> Option<(bool, usize, usize)> {
+    let first = *bytes.first()?;
+    let long_length = |offset: u8| -> Option<usize> {
+        let length_of_length = (first - offset) as usize;
+        let length_bytes = bytes.get(1..1 + length_of_length)?;
+        length_bytes.iter().try_fold(0usize, |length, byte| {
+            length.checked_mul(256)?.checked_add(*byte as usize)
+        })
+    };
+    match first {
+        0x00..=0x7f => Some((false, 0, 1)),
+        0x80..=0xb7 => Some((false, 1, (first - 0x80) as usize)),
+        0xb8..=0xbf => Some((false, 1 + (first - 0xb7) as usize, long_length(0xb7)?)),
+        0xc0..=0xf7 => Some((true, 1, (first - 0xc0) as usize)),
+        0xf8..=0xff => Some((true, 1 + (first - 0xf7) as usize, long_length(0xf7)?)),
+    }
+}
+
+/// The items of an RLP list payload, stopping at the first truncated item.
+///
+/// An item whose length overflows a `usize` counts as truncated.
+fn complete_rlp_items(mut payload: &[u8]) -> (Vec<&[u8]>, &[u8]) {
+    let mut items = vec![];
+    while let Some((_, header_length, payload_length)) = rlp_item_header(payload) {
+        let item_length = match header_length.checked_add(payload_length) {
+            Some(item_length) if item_length <= payload.len() => item_length,
+            _ => break,
+        };
+        items.push(&payload[..item_length]);
+        payload = &payload[item_length..];
+    }
+    (items, payload)
+}
+
+/// The payload of the RLP list at the start of `bytes`, cut short if `bytes` is truncated.
+///
+/// A list whose length overflows a `usize` counts as truncated.
+fn rlp_list_payload(bytes: &[u8]) -> Option<&[u8]> {
+    match rlp_item_header(bytes)? {
+        (true, header_length, payload_length) if header_length <= bytes.len() => {
+            let end = header_length
+                .checked_add(payload_length)
+                .map_or(bytes.len(), |end| end.min(bytes.len()));
+            Some(&bytes[header_length..end])
+        }
+        _ => None,
+    }
+}
+
+/// Pulls the ENRs out of a discv5 NODES response, `[request-id, total, [ENR, ...]]`, with or
+/// without the leading message type byte.
+///
+/// A truncated response yields the ENRs that are complete, and ENRs that fail to decode are
+/// skipped, so the length of the result is the number recovered. It is an error if the body is
+/// not a NODES message at all.
+pub fn extract_enrs_from_nodes_response(
+    bytes: &[u8],
+) -> Result<Vec<Enr<CombinedKey>>, &'static str> {
+    let body = match bytes.first() {
+        Some(&NODES_MESSAGE_TYPE) => &bytes[1..],
+        _ => bytes,
+    };
+    let message = rlp_list_payload(body).ok_or("NODES response is not an RLP list")?;
+    let (fields, remainder) = complete_rlp_items(message);
+    if fields.len() < 2 {
+        return Err("NODES response is missing the request id or total");
+    }
+    let enr_list = fields
+        .get(2)
+        .copied()
+        .or_else(|| Some(remainder).filter(|remainder| !remainder.is_empty()))
+        .ok_or("NODES response has no ENR list")?;
+    let enr_payload = rlp_list_payload(enr_list).ok_or("NODES response ENRs are not a list")?;
+    let (enr_items, _) = complete_rlp_items(enr_payload);
+    Ok(enr_items
+        .into_iter()
+        .filter_map(|item| rlp::decode::<Enr<CombinedKey>>(item).ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_enr(key: &CombinedKey) -> Enr<CombinedKey> {
+        EnrBuilder::new("v4")
+            .ip("10.0.0.1".parse::<IpAddr>().unwrap())
+            .tcp(9000)
+            .udp(9000)
+            .build(key)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_enr_hex() {
+        let enr = build_enr(&CombinedKey::generate_secp256k1());
+        let enr_hex = hex::encode(rlp::encode(&enr));
+        assert_eq!(
+            parse_enr_hex(&enr_hex).unwrap().to_base64(),
+            enr.to_base64()
+        );
+        assert_eq!(
+            parse_enr_hex(&format!("0x{}", enr_hex))
+                .unwrap()
+                .to_base64(),
+            enr.to_base64()
+        );
+        assert_eq!(parse_enr_hex("0xzz").err(), Some(EnrParseError::InvalidHex));
+        assert!(parse_enr_hex("0x00").is_err());
+    }
+
+    #[test]
+    fn test_peer_id_base58() {
+        let keys = vec![
+            CombinedKey::generate_secp256k1(),
+            CombinedKey::generate_ed25519(),
+        ];
+        for key in keys {
+            let peer_id = build_enr(&key).peer_id();
+            let peer_id_str = peer_id_to_base58(&peer_id);
+            assert_eq!(peer_id_str, peer_id.to_string());
+            assert_eq!(peer_id_from_base58(&peer_id_str), Ok(peer_id));
+        }
+        assert!(peer_id_from_base58("not a peer id").is_err());
+    }
+
+    #[test]
+    fn test_enr_typed_ips() {
+        let key = CombinedKey::generate_secp256k1();
+        let enr = build_enr(&key);
+        assert_eq!(enr.enr_ipv4(), Some(Ipv4Addr::new(10, 0, 0, 1)));
+        assert_eq!(enr.enr_ipv6(), None);
+
+        let ip6 = "2001:db8::1".parse::<Ipv6Addr>().unwrap();
+        let enr = EnrBuilder::new("v4")
+            .ip(IpAddr::V6(ip6))
+            .build(&key)
+            .unwrap();
+        assert_eq!(enr.enr_ipv4(), None);
+        assert_eq!(enr.enr_ipv6(), Some(ip6));
+
+        let enr = EnrBuilder::new("v4")
+            .add_value("ip".into(), vec![10, 0, 0])
+            .add_value("ip6".into(), vec![0; 4])
+            .build(&key)
+            .unwrap();
+        assert_eq!(enr.enr_ipv4(), None);
+        assert_eq!(enr.enr_ipv6(), None);
+    }
+
+    #[test]
+    fn test_fit_enr_to_limit_drop_order() {
+        let key = CombinedKey::generate_secp256k1();
+        let fields = |attnets_length: usize| -> BTreeMap<String, Vec<u8>> {
+            let port = 9000u16.to_be_bytes().to_vec();
+            vec![
+                ("id", b"v4".to_vec()),
+                ("ip", vec![10, 0, 0, 1]),
+                ("tcp", port.clone()),
+                ("udp", port.clone()),
+                ("ip6", vec![0x20; 16]),
+                ("tcp6", port.clone()),
+                ("udp6", port.clone()),
+                ("quic", port.clone()),
+                ("quic6", port),
+                ("client", vec![b'x'; 20]),
+                // pads the record to the size under test
+                ("attnets", vec![0xff; attnets_length]),
+            ]
+            .into_iter()
+            .map(|(field, value)| (field.to_string(), value))
+            .collect()
+        };
+        let kept = |attnets_length| -> Vec<String> {
+            let enr = fit_enr_to_limit(fields(attnets_length), 5, &key).unwrap();
+            assert!(enr_encoded_size(&enr) <= MAX_ENR_SIZE);
+            assert_eq!(enr.seq(), 5);
+            enr.iter().map(|(field, _)| field.clone()).collect()
+        };
+        let has = |kept: &[String], field: &str| kept.iter().any(|name| name == field);
+
+        // already fits, nothing is dropped
+        let all = kept(8);
+        assert!(has(&all, "client") && has(&all, "ip6") && has(&all, "quic"));
+        // dropping the custom keys is enough
+        let fitted = kept(70);
+        assert!(!has(&fitted, "client"));
+        assert!(has(&fitted, "ip6") && has(&fitted, "tcp6") && has(&fitted, "quic"));
+        // then the IPv6 fields
+        let fitted = kept(100);
+        assert!(!has(&fitted, "ip6") && !has(&fitted, "tcp6") && !has(&fitted, "udp6"));
+        assert!(has(&fitted, "quic") && has(&fitted, "quic6"));
+        // then quic
+        let fitted = kept(140);
+        assert!(!has(&fitted, "quic") && !has(&fitted, "quic6"));
+        assert!(has(&fitted, "ip") && has(&fitted, "tcp") && has(&fitted, "attnets"));
+        // still too large with every optional field gone
+        assert!(fit_enr_to_limit(fields(170), 5, &key).is_err());
+    }
+
+    #[test]
+    fn test_node_id_bloom() {
+        let enrs: Vec<Enr<CombinedKey>> = (0..1500)
+            .map(|_| build_enr(&CombinedKey::generate_secp256k1()))
+            .collect();
+        let (inserted, others) = enrs.split_at(500);
+        // 10 bits per node and 7 hashes, for a false positive rate of about 0.8%
+        let mut bloom = NodeIdBloom::new(5000, 7);
+        for enr in inserted {
+            bloom.insert(enr);
+        }
+        assert!(inserted.iter().all(|enr| bloom.maybe_seen(enr)));
+        let false_positives = others.iter().filter(|enr| bloom.maybe_seen(enr)).count();
+        assert!(
+            false_positives < 30,
+            "{} false positives in {}",
+            false_positives,
+            others.len()
+        );
+    }
+
+    #[test]
+    fn test_enrs_to_distance_dot_deterministic() {
+        let enrs: Vec<Enr<CombinedKey>> = (0..6)
+            .map(|_| build_enr(&CombinedKey::generate_secp256k1()))
+            .collect();
+        let reference = enrs[0].node_id();
+        let dot = enrs_to_distance_dot(&reference, &enrs);
+        let mut reversed = enrs.clone();
+        reversed.reverse();
+        assert_eq!(enrs_to_distance_dot(&reference, &reversed), dot);
+        assert_eq!(enrs_to_distance_dot(&reference, &enrs), dot);
+
+        // the header, the reference node, a node and an edge per other ENR, and the closing brace
+        assert_eq!(dot.lines().count(), 2 + 2 * 5 + 1);
+        assert!(!dot.contains(&format!("label=\"{}\"", enr_fingerprint(&enrs[0]))));
+        for enr in &enrs[1..] {
+            assert!(dot.contains(&hex::encode(enr.node_id().raw())));
+        }
+    }
+
+    #[test]
+    fn test_extract_enrs_from_nodes_response() {
+        let enrs = vec![
+            build_enr(&CombinedKey::generate_secp256k1()),
+            build_enr(&CombinedKey::generate_secp256k1()),
+        ];
+        let mut stream = rlp::RlpStream::new_list(3);
+        stream.append(&1u64);
+        stream.append(&1u64);
+        stream.begin_list(enrs.len());
+        for enr in &enrs {
+            stream.append(enr);
+        }
+        let mut packet = vec![0x04];
+        packet.extend(stream.out());
+
+        let extracted = extract_enrs_from_nodes_response(&packet).unwrap();
+        assert_eq!(extracted.len(), 2);
+        assert_eq!(extracted[1].to_base64(), enrs[1].to_base64());
+        // the second ENR is cut short
+        assert_eq!(
+            extract_enrs_from_nodes_response(&packet[..packet.len() - 5])
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(extract_enrs_from_nodes_response(&[0x04, 0x01]).is_err());
+    }
+
+    #[test]
+    fn test_extract_enrs_from_nodes_response_length_overflow() {
+        // a list header claiming a payload of 2^64 - 1 bytes
+        let huge_list = [0xff; 9];
+        let mut packet = vec![0x04];
+        packet.extend_from_slice(&huge_list);
+        assert!(extract_enrs_from_nodes_response(&packet).is_err());
+
+        // a well formed message whose ENR list has the huge header
+        let mut packet = vec![0x04, 0xc0 + 2 + huge_list.len() as u8, 0x01, 0x01];
+        packet.extend_from_slice(&huge_list);
+        assert_eq!(extract_enrs_from_nodes_response(&packet).unwrap().len(), 0);
+
+        // a huge item inside the ENR list
+        let mut packet = vec![0x04, 0xc0 + 3 + huge_list.len() as u8, 0x01, 0x01];
+        packet.push(0xc0 + huge_list.len() as u8);
+        packet.extend_from_slice(&huge_list);
+        assert_eq!(extract_enrs_from_nodes_response(&packet).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_enr_to_peer_routing_entry() {
+        let key = CombinedKey::generate_secp256k1();
+        let enr = EnrBuilder::new("v4")
+            .ip("10.0.0.1".parse::<IpAddr>().unwrap())
+            .tcp(9000)
+            .udp(9000)
+            .add_value("quic".into(), 9001u16.to_be_bytes().to_vec())
+            .build(&key)
+            .unwrap();
+        let (peer_id, multiaddrs) = enr_to_peer_routing_entry(&enr);
+        assert_eq!(peer_id, enr.peer_id());
+        assert_eq!(multiaddrs.len(), 2);
+        for multiaddr in multiaddrs {
+            match multiaddr.iter().last() {
+                Some(Protocol::P2p(multihash)) => {
+                    assert_eq!(PeerId::from_multihash(multihash).unwrap(), peer_id)
+                }
+                _ => panic!("{} has no /p2p suffix", multiaddr),
+            }
+        }
+
+        let unreachable = EnrBuilder::new("v4").build(&key).unwrap();
+        assert!(enr_to_peer_routing_entry(&unreachable).1.is_empty());
+    }
+
+    #[cfg(all(feature = "local", feature = "testing"))]
+    #[tokio::test]
+    async fn test_discovery_between_local_nodes() {
+        use crate::utils::local_test_enr;
+        use discv5_local::{Discv5, Discv5ConfigBuilder, Discv5Event};
+        use futures::StreamExt;
+        use std::net::{SocketAddr, UdpSocket};
+        use std::time::Duration;
+
+        // free ports from the OS rather than fixed ones, so parallel runs do not collide. The
+        // sockets are held until all three are picked so that the ports differ.
+        let sockets: Vec<UdpSocket> = (0..3)
+            .map(|_| UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap())
+            .collect();
+        let ports: Vec<u16> = sockets
+            .iter()
+            .map(|socket| socket.local_addr().unwrap().port())
+            .collect();
+        drop(sockets);
+
+        let local_node = |port: u16| {
+            let key = CombinedKey::generate_secp256k1();
+            let enr = local_test_enr(&key, port);
+            let discv5 = Discv5::new(
+                enr.clone(),
+                key,
+                Discv5ConfigBuilder::new().build(),
+                SocketAddr::new(Ipv4Addr::LOCALHOST.into(), port),
+            )
+            .unwrap();
+            (discv5, enr)
+        };
+        let (mut bootnode, bootnode_enr) = local_node(ports[0]);
+        let (mut node, _) = local_node(ports[1]);
+        let (mut other, other_enr) = local_node(ports[2]);
+
+        // `other` introduces itself to the bootnode, so only the bootnode knows its ENR
+        other.add_enr(bootnode_enr.clone()).unwrap();
+        other.find_node(bootnode_enr.node_id());
+        tokio::time::timeout(Duration::from_secs(30), async {
+            loop {
+                tokio::select! {
+                    _ = bootnode.next() => (),
+                    _ = node.next() => (),
+                    Some(event) = other.next() => {
+                        if let Discv5Event::FindNodeResult { .. } = event {
+                            break;
+                        }
+                    }
+                }
+            }
+        })
+        .await
+        .expect("introduction query timed out");
+
+        node.add_enr(bootnode_enr.clone()).unwrap();
+        assert!(node
+            .enr_entries()
+            .all(|enr| enr.node_id() != other_enr.node_id()));
+        // the pinned discv5 exposes no `find_peers`, `find_node` is its peer lookup query
+        node.find_node(other_enr.node_id());
+
+        let closer_peers = tokio::time::timeout(Duration::from_secs(30), async {
+            loop {
+                tokio::select! {
+                    _ = bootnode.next() => (),
+                    _ = other.next() => (),
+                    Some(event) = node.next() => {
+                        if let Discv5Event::FindNodeResult { closer_peers, .. } = event {
+                            break closer_peers;
+                        }
+                    }
+                }
+            }
+        })
+        .await
+        .expect("find node query timed out");
+
+        assert!(closer_peers.contains(&other_enr.node_id()));
+        let discovered = node
+            .enr_entries()
+            .find(|enr| enr.node_id() == other_enr.node_id())
+            .expect("enr discovered through the bootnode");
+        assert_eq!(discovered.peer_id(), other_enr.peer_id());
+    }
+}
@@ -0,0 +1,83 @@
+use super::{
+    enr_has_routable_ip, enr_key_type, enr_matches_fork_digest, enr_supports_quic,
+    get_attnets_from_enr, KeyType,
+};
+#[cfg(not(feature = "local"))]
+use discv5::enr::{CombinedKey, Enr};
+#[cfg(feature = "local")]
+use discv5_local::enr::{CombinedKey, Enr};
+
+/// A reusable ENR predicate built from the individual filters.
+///
+/// An ENR matches if it passes every configured filter. A filter with nothing configured matches
+/// every ENR.
+#[derive(Debug, Clone, Default)]
+pub struct EnrFilter {
+    fork_digest: Option<[u8; 4]>,
+    subnets: Vec<u64>,
+    key_type: Option<KeyType>,
+    requires_quic: bool,
+    routable_only: bool,
+}
+
+impl EnrFilter {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn fork_digest(mut self, fork_digest: [u8; 4]) -> Self {
+        self.fork_digest = Some(fork_digest);
+        self
+    }
+
+    /// Requires the attestation subnet to be set in `attnets`. Can be called more than once.
+    pub fn subnet(mut self, subnet_id: u64) -> Self {
+        self.subnets.push(subnet_id);
+        self
+    }
+
+    pub fn key_type(mut self, key_type: KeyType) -> Self {
+        self.key_type = Some(key_type);
+        self
+    }
+
+    pub fn requires_quic(mut self) -> Self {
+        self.requires_quic = true;
+        self
+    }
+
+    pub fn routable_only(mut self) -> Self {
+        self.routable_only = true;
+        self
+    }
+
+    pub fn matches(&self, enr: &Enr<CombinedKey>) -> bool {
+        if let Some(fork_digest) = self.fork_digest {
+            if !enr_matches_fork_digest(enr, fork_digest) {
+                return false;
+            }
+        }
+        if !self.subnets.is_empty() {
+            let attnets = get_attnets_from_enr(enr);
+            if !self
+                .subnets
+                .iter()
+                .all(|subnet_id| attnets.contains(subnet_id))
+            {
+                return false;
+            }
+        }
+        if let Some(key_type) = self.key_type {
+            if enr_key_type(enr) != key_type {
+                return false;
+            }
+        }
+        if self.requires_quic && !enr_supports_quic(enr) {
+            return false;
+        }
+        if self.routable_only && !enr_has_routable_ip(enr) {
+            return false;
+        }
+        true
+    }
+}
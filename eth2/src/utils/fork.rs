@@ -0,0 +1,697 @@
+use super::{enr_matches_fork_digest, get_fork_id_from_enr, is_eth2_enr};
+use crate::ssz::{Decode, DecodeError, Encode};
+use crate::types::{ChainSpec, EnrForkId, Epoch, EthSpec, Hash256};
+#[cfg(not(feature = "local"))]
+use discv5::enr::{CombinedKey, Enr};
+#[cfg(feature = "local")]
+use discv5_local::enr::{CombinedKey, Enr};
+use std::collections::BTreeMap;
+
+/// The named consensus forks, in activation order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ForkName {
+    Phase0,
+    Altair,
+    Bellatrix,
+    Capella,
+    Deneb,
+    Electra,
+    Fulu,
+}
+
+/// The fork digest of every fork on the major public networks, including the Fulu blob parameter
+/// only forks that change the digest but not the fork version.
+///
+/// Listing each fork rather than only the latest also recognizes nodes that lag behind an
+/// upgrade. Forks that activated at genesis alongside a later one never appear in an ENR, so
+/// Hoodi starts at Deneb. Holesky was retired in 2025 and is not listed.
+pub const KNOWN_NETWORK_DIGESTS: [(&str, ForkName, [u8; 4]); 30] = [
+    ("mainnet", ForkName::Phase0, [0xb5, 0x30, 0x3f, 0x2a]),
+    ("mainnet", ForkName::Altair, [0xaf, 0xca, 0xab, 0xa0]),
+    ("mainnet", ForkName::Bellatrix, [0x4a, 0x26, 0xc5, 0x8b]),
+    ("mainnet", ForkName::Capella, [0xbb, 0xa4, 0xda, 0x96]),
+    ("mainnet", ForkName::Deneb, [0x6a, 0x95, 0xa1, 0xa9]),
+    ("mainnet", ForkName::Electra, [0xad, 0x53, 0x2c, 0xeb]),
+    ("mainnet", ForkName::Fulu, [0xcc, 0x2c, 0x5c, 0xdb]),
+    ("mainnet", ForkName::Fulu, [0xcb, 0x0d, 0x1a, 0xcc]),
+    ("mainnet", ForkName::Fulu, [0x8c, 0x9f, 0x62, 0xfe]),
+    ("sepolia", ForkName::Phase0, [0xa8, 0xfe, 0xe8, 0xee]),
+    ("sepolia", ForkName::Altair, [0x60, 0x72, 0x5c, 0xa1]),
+    ("sepolia", ForkName::Bellatrix, [0x36, 0xfa, 0x50, 0x13]),
+    ("sepolia", ForkName::Capella, [0x47, 0xeb, 0x72, 0xb3]),
+    ("sepolia", ForkName::Deneb, [0xd3, 0x1f, 0x61, 0x91]),
+    ("sepolia", ForkName::Electra, [0x14, 0x04, 0x5b, 0x5a]),
+    ("sepolia", ForkName::Fulu, [0x7e, 0x0d, 0x34, 0x47]),
+    ("sepolia", ForkName::Fulu, [0xfd, 0x32, 0xb6, 0x22]),
+    ("sepolia", ForkName::Fulu, [0x74, 0xd0, 0x14, 0x59]),
+    ("gnosis", ForkName::Phase0, [0xbc, 0x9a, 0x68, 0x64]),
+    ("gnosis", ForkName::Altair, [0x56, 0xfd, 0xb5, 0xe0]),
+    ("gnosis", ForkName::Bellatrix, [0x82, 0x4b, 0xe4, 0x31]),
+    ("gnosis", ForkName::Capella, [0x21, 0xa6, 0xf8, 0x36]),
+    ("gnosis", ForkName::Deneb, [0x3e, 0xbf, 0xd4, 0x84]),
+    ("gnosis", ForkName::Electra, [0x7d, 0x5a, 0xab, 0x40]),
+    ("gnosis", ForkName::Fulu, [0x32, 0x37, 0xda, 0xb6]),
+    ("hoodi", ForkName::Deneb, [0xd2, 0xf1, 0x99, 0x7f]),
+    ("hoodi", ForkName::Electra, [0x82, 0x55, 0x6a, 0x32]),
+    ("hoodi", ForkName::Fulu, [0xe2, 0xab, 0xcc, 0xa4]),
+    ("hoodi", ForkName::Fulu, [0xae, 0x9f, 0x70, 0xa0]),
+    ("hoodi", ForkName::Fulu, [0xc6, 0xec, 0xb7, 0x6c]),
+];
+
+/// The forks of a network with their activation epochs and fork versions, in activation order.
+///
+/// The pinned `ChainSpec` predates Altair, so `fork_schedule` only yields the genesis fork. The
+/// fork aware functions therefore take a schedule rather than a `ChainSpec`. A schedule with the
+/// later forks has to be built with `ForkSchedule::new`, for example from the network's published
+/// config, and `&fork_schedule(spec)` gives the spec's own schedule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForkSchedule {
+    forks: Vec<(ForkName, Epoch, [u8; 4])>,
+}
+
+impl ForkSchedule {
+    /// Creates a schedule from `(fork, activation epoch, fork version)` entries in any order.
+    pub fn new(mut forks: Vec<(ForkName, Epoch, [u8; 4])>) -> Self {
+        forks.sort_by_key(|(_, epoch, _)| epoch.as_u64());
+        Self { forks }
+    }
+
+    /// The scheduled forks, in activation order.
+    pub fn forks(&self) -> &[(ForkName, Epoch, [u8; 4])] {
+        &self.forks
+    }
+}
+
+/// The forks scheduled by `spec`, which for the pinned phase0 `ChainSpec` is just the genesis fork.
+pub fn fork_schedule(spec: &ChainSpec) -> ForkSchedule {
+    ForkSchedule::new(vec![(
+        ForkName::Phase0,
+        Epoch::new(0),
+        spec.genesis_fork_version,
+    )])
+}
+
+/// The `EnrForkId` advertised while each scheduled fork is active, in schedule order.
+///
+/// The next fork fields point at the following scheduled fork, or at the current version and
+/// the far future epoch for the last one.
+pub fn scheduled_fork_ids(
+    genesis_validators_root: Hash256,
+    schedule: &ForkSchedule,
+) -> Vec<EnrForkId> {
+    let forks = schedule.forks();
+    forks
+        .iter()
+        .enumerate()
+        .map(|(i, (_, _, fork_version))| {
+            let (next_fork_epoch, next_fork_version) = forks
+                .get(i + 1)
+                .map(|(_, epoch, version)| (*epoch, *version))
+                .unwrap_or((Epoch::new(u64::MAX), *fork_version));
+            EnrForkId {
+                fork_digest: ChainSpec::compute_fork_digest(*fork_version, genesis_validators_root),
+                next_fork_version,
+                next_fork_epoch,
+            }
+        })
+        .collect()
+}
+
+/// The `EnrForkId` for the genesis fork, computed directly from the spec.
+///
+/// Unlike `get_default_fork_id` this carries the real fork digest for the network.
+pub fn genesis_fork_id(genesis_validators_root: Hash256, spec: &ChainSpec) -> EnrForkId {
+    scheduled_fork_ids(genesis_validators_root, &fork_schedule(spec))
+        .into_iter()
+        .next()
+        .expect("the schedule always contains the genesis fork")
+}
+
+/// The scheduled fork version whose digest is `fork_digest`, or `None` if none matches.
+pub fn fork_version_for_digest(
+    fork_digest: [u8; 4],
+    genesis_validators_root: Hash256,
+    schedule: &ForkSchedule,
+) -> Option<[u8; 4]> {
+    schedule
+        .forks()
+        .iter()
+        .map(|(_, _, fork_version)| *fork_version)
+        .find(|fork_version| {
+            ChainSpec::compute_fork_digest(*fork_version, genesis_validators_root) == fork_digest
+        })
+}
+
+/// The scheduled fork whose digest matches the one advertised, or `None` if it is unrecognized.
+pub fn fork_name_from_fork_id(
+    fork_id: &EnrForkId,
+    genesis_validators_root: Hash256,
+    schedule: &ForkSchedule,
+) -> Option<ForkName> {
+    schedule
+        .forks()
+        .iter()
+        .find(|(_, _, fork_version)| {
+            ChainSpec::compute_fork_digest(*fork_version, genesis_validators_root)
+                == fork_id.fork_digest
+        })
+        .map(|(fork_name, _, _)| *fork_name)
+}
+
+/// Returns true if the next fork fields match those expected for the fork of the advertised digest.
+///
+/// Unrecognized digests are never consistent.
+pub fn fork_id_self_consistent(
+    fork_id: &EnrForkId,
+    genesis_validators_root: Hash256,
+    schedule: &ForkSchedule,
+) -> bool {
+    scheduled_fork_ids(genesis_validators_root, schedule)
+        .iter()
+        .find(|expected| expected.fork_digest == fork_id.fork_digest)
+        .map_or(false, |expected| {
+            expected.next_fork_version == fork_id.next_fork_version
+                && expected.next_fork_epoch == fork_id.next_fork_epoch
+        })
+}
+
+/// Finds the scheduled fork version whose digest matches the one advertised in the ENR.
+///
+/// Returns `None` if the ENR has no eth2 field or the digest belongs to an unknown network.
+pub fn current_fork_version_from_enr(
+    enr: &Enr<CombinedKey>,
+    genesis_validators_root: Hash256,
+    schedule: &ForkSchedule,
+) -> Option<[u8; 4]> {
+    let enr_fork_id = get_fork_id_from_enr(enr)?;
+    fork_version_for_digest(enr_fork_id.fork_digest, genesis_validators_root, schedule)
+}
+
+/// The unix time in seconds at which the advertised next fork activates.
+///
+/// Returns `None` for the far future epoch sentinel.
+pub fn next_fork_time<E: EthSpec>(
+    fork_id: &EnrForkId,
+    genesis_time: u64,
+    spec: &ChainSpec,
+) -> Option<u64> {
+    if fork_id.next_fork_epoch == spec.far_future_epoch {
+        return None;
+    }
+    let millis = fork_id
+        .next_fork_epoch
+        .as_u64()
+        .checked_mul(E::slots_per_epoch())?
+        .checked_mul(spec.milliseconds_per_slot)?;
+    genesis_time.checked_add(millis / 1000)
+}
+
+/// Seconds from `now` until the advertised next fork, negative if it has already happened.
+///
+/// Returns `None` for the far future epoch sentinel. Differences beyond the range of `i64` are
+/// clamped to its bounds. `E` supplies the slots per epoch, which the pinned `ChainSpec` does not
+/// hold.
+pub fn seconds_until_next_fork<E: EthSpec>(
+    fork_id: &EnrForkId,
+    genesis_time: u64,
+    now: u64,
+    spec: &ChainSpec,
+) -> Option<i64> {
+    let fork_time = next_fork_time::<E>(fork_id, genesis_time, spec)?;
+    let seconds = i128::from(fork_time) - i128::from(now);
+    Some(seconds.max(i128::from(i64::MIN)).min(i128::from(i64::MAX)) as i64)
+}
+
+/// Pairs each ENR's sequence number with its advertised fork id, sorted by sequence number.
+///
+/// Intended for the ENR history of a single node.
+pub fn fork_id_timeline(enrs: &[Enr<CombinedKey>]) -> Vec<(u64, Option<EnrForkId>)> {
+    let mut timeline: Vec<(u64, Option<EnrForkId>)> = enrs
+        .iter()
+        .map(|enr| (enr.seq(), get_fork_id_from_enr(enr)))
+        .collect();
+    timeline.sort_by_key(|(seq, _)| *seq);
+    timeline
+}
+
+/// Tallies the ENRs per advertised fork digest.
+///
+/// Records without a decodable eth2 field are counted under `None`, so they can never be mistaken
+/// for a real digest.
+pub fn fork_digest_counts(enrs: &[Enr<CombinedKey>]) -> BTreeMap<Option<[u8; 4]>, usize> {
+    let mut counts = BTreeMap::new();
+    for enr in enrs {
+        let fork_digest = get_fork_id_from_enr(enr).map(|enr_fork_id| enr_fork_id.fork_digest);
+        *counts.entry(fork_digest).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Renders the counts from `fork_digest_counts` as a table, one digest per line, largest first.
+pub fn format_fork_digest_counts(counts: &BTreeMap<Option<[u8; 4]>, usize>) -> String {
+    let mut rows: Vec<(String, usize)> = counts
+        .iter()
+        .map(|(fork_digest, count)| {
+            let digest = fork_digest.map_or_else(|| "none".to_string(), hex::encode);
+            (digest, *count)
+        })
+        .collect();
+    rows.sort_by(|a, b| b.1.cmp(&a.1));
+    let count_width = rows
+        .iter()
+        .map(|(_, count)| count.to_string().len())
+        .chain(std::iter::once("count".len()))
+        .max()
+        .unwrap_or(0);
+
+    let mut table = format!(
+        "{:<11} {:>width$}\n",
+        "fork_digest",
+        "count",
+        width = count_width
+    );
+    for (digest, count) in rows {
+        table.push_str(&format!(
+            "{:<11} {:>width$}\n",
+            digest,
+            count,
+            width = count_width
+        ));
+    }
+    table
+}
+
+/// Returns true if the advertised digest matches a fork in either schedule.
+///
+/// Lets a node keep peering across a coordinated spec change.
+pub fn fork_id_recognized_by_either(
+    fork_id: &EnrForkId,
+    genesis_validators_root: Hash256,
+    old: &ForkSchedule,
+    new: &ForkSchedule,
+) -> bool {
+    fork_name_from_fork_id(fork_id, genesis_validators_root, old).is_some()
+        || fork_name_from_fork_id(fork_id, genesis_validators_root, new).is_some()
+}
+
+/// SSZ encodes the fork ids as a variable length list.
+pub fn encode_fork_id_list(ids: &[EnrForkId]) -> Vec<u8> {
+    ids.to_vec().as_ssz_bytes()
+}
+
+/// Decodes a list of fork ids written by `encode_fork_id_list`.
+pub fn decode_fork_id_list(bytes: &[u8]) -> Result<Vec<EnrForkId>, DecodeError> {
+    Vec::<EnrForkId>::from_ssz_bytes(bytes)
+}
+
+/// Returns true if the peer's next fork is at most `horizon` epochs away and its next version
+/// differs from `my_next_version`.
+///
+/// A next fork epoch that has already passed counts as within the horizon. The far future epoch
+/// sentinel means no fork is scheduled and never counts, however large the horizon.
+pub fn peer_forking_away(
+    fork_id: &EnrForkId,
+    my_next_version: [u8; 4],
+    current_epoch: Epoch,
+    horizon: u64,
+) -> bool {
+    fork_id.next_fork_version != my_next_version
+        && fork_id.next_fork_epoch.as_u64() != u64::MAX
+        && fork_id.next_fork_epoch.as_u64() <= current_epoch.as_u64().saturating_add(horizon)
+}
+
+/// The name of the network in `KNOWN_NETWORK_DIGESTS` with the advertised digest.
+///
+/// Any fork of the network matches, so nodes still on an earlier fork are identified too.
+pub fn identify_network(fork_id: &EnrForkId) -> Option<&'static str> {
+    KNOWN_NETWORK_DIGESTS
+        .iter()
+        .find(|(_, _, fork_digest)| *fork_digest == fork_id.fork_digest)
+        .map(|(network, _, _)| *network)
+}
+
+/// Heuristically flags ENRs whose advertised next fork is more than `stale_margin` epochs in the
+/// past, suggesting the node missed the fork and is offline or not updating its record.
+///
+/// The far future epoch sentinel means no fork is scheduled and is never stale, nor are ENRs
+/// without an eth2 field. A node that upgraded but has not yet re-advertised looks stale too.
+pub fn enr_likely_stale(enr: &Enr<CombinedKey>, current_epoch: Epoch, stale_margin: u64) -> bool {
+    match get_fork_id_from_enr(enr) {
+        Some(enr_fork_id) if enr_fork_id.next_fork_epoch.as_u64() != u64::MAX => {
+            enr_fork_id
+                .next_fork_epoch
+                .as_u64()
+                .saturating_add(stale_margin)
+                < current_epoch.as_u64()
+        }
+        _ => false,
+    }
+}
+
+/// The fraction of ENRs with an eth2 field that advertise `my_digest`, or 0 if none have one.
+pub fn fork_agreement_ratio(enrs: &[Enr<CombinedKey>], my_digest: [u8; 4]) -> f64 {
+    let eth2_enrs: Vec<&Enr<CombinedKey>> = enrs.iter().filter(|enr| is_eth2_enr(enr)).collect();
+    if eth2_enrs.is_empty() {
+        return 0.0;
+    }
+    let agreeing = eth2_enrs
+        .iter()
+        .filter(|enr| enr_matches_fork_digest(enr, my_digest))
+        .count();
+    agreeing as f64 / eth2_enrs.len() as f64
+}
+
+/// Returns true if the advertised digest belongs to a fork in the schedule.
+///
+/// The schedule holds the past and current forks plus any scheduled upgrade, so a digest outside
+/// of it points to a fork this node does not know about.
+pub fn fork_within_known_schedule(
+    fork_id: &EnrForkId,
+    genesis_validators_root: Hash256,
+    schedule: &ForkSchedule,
+) -> bool {
+    fork_name_from_fork_id(fork_id, genesis_validators_root, schedule).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MainnetEthSpec;
+    #[cfg(not(feature = "local"))]
+    use discv5::enr::EnrBuilder;
+    #[cfg(feature = "local")]
+    use discv5_local::enr::EnrBuilder;
+
+    fn mainnet_genesis_validators_root() -> Hash256 {
+        Hash256::from_slice(
+            &hex::decode("4b363db94e286120d76eb905340fdd4e54bfe9f06bf33ff6cf5ad27f511bfe95")
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_genesis_fork_id() {
+        let spec = ChainSpec::mainnet();
+        let fork_id = genesis_fork_id(mainnet_genesis_validators_root(), &spec);
+        assert_eq!(fork_id.fork_digest, [0xb5, 0x30, 0x3f, 0x2a]);
+        assert_eq!(fork_id.next_fork_version, spec.genesis_fork_version);
+        assert_eq!(fork_id.next_fork_epoch, spec.far_future_epoch);
+    }
+
+    #[test]
+    fn test_seconds_until_next_fork() {
+        let spec = ChainSpec::mainnet();
+        let fork_id = |next_fork_epoch: u64| EnrForkId {
+            fork_digest: [0; 4],
+            next_fork_version: [0; 4],
+            next_fork_epoch: Epoch::new(next_fork_epoch),
+        };
+        let seconds = |fork_id: &EnrForkId, genesis_time: u64, now: u64| {
+            seconds_until_next_fork::<MainnetEthSpec>(fork_id, genesis_time, now, &spec)
+        };
+        // an epoch is 32 slots of 12 seconds
+        assert_eq!(seconds(&fork_id(1), 1000, 1100), Some(284));
+        assert_eq!(seconds(&fork_id(1), 1000, 2000), Some(-616));
+        assert_eq!(seconds(&fork_id(0), 0, u64::MAX), Some(i64::MIN));
+        assert_eq!(seconds(&fork_id(1), u64::MAX - 384, 0), Some(i64::MAX));
+        assert_eq!(seconds(&fork_id(u64::MAX), 0, 0), None);
+    }
+
+    #[test]
+    fn test_peer_forking_away() {
+        let fork_id = |next_fork_version, next_fork_epoch: u64| EnrForkId {
+            fork_digest: [0; 4],
+            next_fork_version,
+            next_fork_epoch: Epoch::new(next_fork_epoch),
+        };
+        let current_epoch = Epoch::new(100);
+        assert!(peer_forking_away(
+            &fork_id([1, 0, 0, 0], 110),
+            [0; 4],
+            current_epoch,
+            10
+        ));
+        assert!(peer_forking_away(
+            &fork_id([1, 0, 0, 0], 50),
+            [0; 4],
+            current_epoch,
+            10
+        ));
+        assert!(!peer_forking_away(
+            &fork_id([1, 0, 0, 0], 111),
+            [0; 4],
+            current_epoch,
+            10
+        ));
+        assert!(!peer_forking_away(
+            &fork_id([0; 4], 110),
+            [0; 4],
+            current_epoch,
+            10
+        ));
+        assert!(!peer_forking_away(
+            &fork_id([1, 0, 0, 0], u64::MAX),
+            [0; 4],
+            current_epoch,
+            u64::MAX
+        ));
+    }
+
+    #[test]
+    fn test_fork_id_list_round_trip() {
+        let genesis = genesis_fork_id(mainnet_genesis_validators_root(), &ChainSpec::mainnet());
+        let next = EnrForkId {
+            fork_digest: [1, 2, 3, 4],
+            next_fork_version: [5, 6, 7, 8],
+            next_fork_epoch: Epoch::new(42),
+        };
+        let lists = vec![vec![], vec![genesis.clone()], vec![genesis, next]];
+        for ids in lists {
+            let bytes = encode_fork_id_list(&ids);
+            assert_eq!(decode_fork_id_list(&bytes).unwrap(), ids);
+        }
+        assert!(decode_fork_id_list(&[0; 3]).is_err());
+    }
+
+    #[test]
+    fn test_identify_network() {
+        let fork_id = |fork_digest| EnrForkId {
+            fork_digest,
+            next_fork_version: [0; 4],
+            next_fork_epoch: Epoch::new(0),
+        };
+        // current and lagging mainnet nodes
+        assert_eq!(
+            identify_network(&fork_id([0xad, 0x53, 0x2c, 0xeb])),
+            Some("mainnet")
+        );
+        assert_eq!(
+            identify_network(&fork_id([0xbb, 0xa4, 0xda, 0x96])),
+            Some("mainnet")
+        );
+        assert_eq!(
+            identify_network(&fork_id([0xd3, 0x1f, 0x61, 0x91])),
+            Some("sepolia")
+        );
+        assert_eq!(
+            identify_network(&fork_id([0xbc, 0x9a, 0x68, 0x64])),
+            Some("gnosis")
+        );
+        // mainnet after the second blob parameter only fork
+        assert_eq!(
+            identify_network(&fork_id([0x8c, 0x9f, 0x62, 0xfe])),
+            Some("mainnet")
+        );
+        assert_eq!(
+            identify_network(&fork_id([0xe2, 0xab, 0xcc, 0xa4])),
+            Some("hoodi")
+        );
+        // holesky deneb
+        assert_eq!(identify_network(&fork_id([0x69, 0xae, 0x0e, 0x99])), None);
+    }
+
+    #[test]
+    fn test_known_network_digests_match_spec_digest() {
+        let genesis_validators_root = mainnet_genesis_validators_root();
+        let versions = [
+            [0, 0, 0, 0],
+            [1, 0, 0, 0],
+            [2, 0, 0, 0],
+            [3, 0, 0, 0],
+            [4, 0, 0, 0],
+            [5, 0, 0, 0],
+        ];
+        let mainnet: Vec<[u8; 4]> = KNOWN_NETWORK_DIGESTS
+            .iter()
+            .filter(|(network, _, _)| *network == "mainnet")
+            .map(|(_, _, fork_digest)| *fork_digest)
+            .collect();
+        let mut expected: Vec<[u8; 4]> = versions
+            .iter()
+            .map(|version| ChainSpec::compute_fork_digest(*version, genesis_validators_root))
+            .collect();
+        // from Fulu the digest is xored with the hash of the active blob parameters
+        let fulu_digest = ChainSpec::compute_fork_digest([6, 0, 0, 0], genesis_validators_root);
+        for (epoch, max_blobs_per_block) in [(364032u64, 9u64), (412672, 15), (419072, 21)].iter() {
+            let mut preimage = epoch.to_le_bytes().to_vec();
+            preimage.extend_from_slice(&max_blobs_per_block.to_le_bytes());
+            let blob_parameters_hash = eth2_hashing::hash(&preimage);
+            let mut fork_digest = fulu_digest;
+            for (byte, mask) in fork_digest.iter_mut().zip(blob_parameters_hash.iter()) {
+                *byte ^= mask;
+            }
+            expected.push(fork_digest);
+        }
+        assert_eq!(mainnet, expected);
+    }
+
+    fn mainnet_fork_schedule() -> ForkSchedule {
+        ForkSchedule::new(vec![
+            (ForkName::Phase0, Epoch::new(0), [0, 0, 0, 0]),
+            (ForkName::Altair, Epoch::new(74240), [1, 0, 0, 0]),
+            (ForkName::Bellatrix, Epoch::new(144896), [2, 0, 0, 0]),
+            (ForkName::Capella, Epoch::new(194048), [3, 0, 0, 0]),
+            (ForkName::Deneb, Epoch::new(269568), [4, 0, 0, 0]),
+            (ForkName::Electra, Epoch::new(364032), [5, 0, 0, 0]),
+        ])
+    }
+
+    fn fork_id_with_digest(fork_digest: [u8; 4]) -> EnrForkId {
+        EnrForkId {
+            fork_digest,
+            next_fork_version: [0; 4],
+            next_fork_epoch: Epoch::new(0),
+        }
+    }
+
+    #[test]
+    fn test_scheduled_fork_ids() {
+        let fork_ids =
+            scheduled_fork_ids(mainnet_genesis_validators_root(), &mainnet_fork_schedule());
+        assert_eq!(fork_ids.len(), 6);
+        assert_eq!(fork_ids[0].fork_digest, [0xb5, 0x30, 0x3f, 0x2a]);
+        assert_eq!(fork_ids[0].next_fork_version, [1, 0, 0, 0]);
+        assert_eq!(fork_ids[0].next_fork_epoch, Epoch::new(74240));
+        assert_eq!(fork_ids[5].fork_digest, [0xad, 0x53, 0x2c, 0xeb]);
+        assert_eq!(fork_ids[5].next_fork_version, [5, 0, 0, 0]);
+        assert_eq!(fork_ids[5].next_fork_epoch, Epoch::new(u64::MAX));
+    }
+
+    #[test]
+    fn test_current_fork_version_from_enr() {
+        let root = mainnet_genesis_validators_root();
+        let key = CombinedKey::generate_secp256k1();
+        let capella = fork_id_with_digest([0xbb, 0xa4, 0xda, 0x96]);
+        let enr = EnrBuilder::new("v4")
+            .add_value("eth2".into(), capella.as_ssz_bytes())
+            .build(&key)
+            .unwrap();
+        assert_eq!(
+            current_fork_version_from_enr(&enr, root, &mainnet_fork_schedule()),
+            Some([3, 0, 0, 0])
+        );
+        let spec_schedule = fork_schedule(&ChainSpec::mainnet());
+        assert_eq!(
+            current_fork_version_from_enr(&enr, root, &spec_schedule),
+            None
+        );
+        let without_eth2 = EnrBuilder::new("v4").build(&key).unwrap();
+        assert_eq!(
+            current_fork_version_from_enr(&without_eth2, root, &mainnet_fork_schedule()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_fork_name_from_fork_id() {
+        let schedule = mainnet_fork_schedule();
+        let root = mainnet_genesis_validators_root();
+        for (network, fork_name, fork_digest) in KNOWN_NETWORK_DIGESTS.iter() {
+            // the schedule ends at Electra
+            let expected = if *network == "mainnet" && *fork_name != ForkName::Fulu {
+                Some(*fork_name)
+            } else {
+                None
+            };
+            assert_eq!(
+                fork_name_from_fork_id(&fork_id_with_digest(*fork_digest), root, &schedule),
+                expected
+            );
+        }
+        // the spec schedule only knows the genesis fork
+        let spec_schedule = fork_schedule(&ChainSpec::mainnet());
+        assert_eq!(
+            fork_name_from_fork_id(
+                &fork_id_with_digest([0xb5, 0x30, 0x3f, 0x2a]),
+                root,
+                &spec_schedule
+            ),
+            Some(ForkName::Phase0)
+        );
+        assert_eq!(
+            fork_name_from_fork_id(
+                &fork_id_with_digest([0xbb, 0xa4, 0xda, 0x96]),
+                root,
+                &spec_schedule
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_fork_id_self_consistent() {
+        let schedule = mainnet_fork_schedule();
+        let root = mainnet_genesis_validators_root();
+        for fork_id in scheduled_fork_ids(root, &schedule) {
+            assert!(fork_id_self_consistent(&fork_id, root, &schedule));
+        }
+        // a Capella digest announcing Electra rather than Deneb
+        let contradictory = EnrForkId {
+            fork_digest: [0xbb, 0xa4, 0xda, 0x96],
+            next_fork_version: [5, 0, 0, 0],
+            next_fork_epoch: Epoch::new(364032),
+        };
+        assert!(!fork_id_self_consistent(&contradictory, root, &schedule));
+        let wrong_epoch = EnrForkId {
+            next_fork_version: [4, 0, 0, 0],
+            next_fork_epoch: Epoch::new(1),
+            ..contradictory.clone()
+        };
+        assert!(!fork_id_self_consistent(&wrong_epoch, root, &schedule));
+        let unknown = fork_id_with_digest([0; 4]);
+        assert!(!fork_id_self_consistent(&unknown, root, &schedule));
+    }
+
+    #[test]
+    fn test_fork_id_recognized_by_either() {
+        let root = mainnet_genesis_validators_root();
+        let new = mainnet_fork_schedule();
+        let old = ForkSchedule::new(new.forks()[..5].to_vec());
+        let deneb = fork_id_with_digest([0x6a, 0x95, 0xa1, 0xa9]);
+        let electra = fork_id_with_digest([0xad, 0x53, 0x2c, 0xeb]);
+        assert!(fork_id_recognized_by_either(&deneb, root, &old, &new));
+        assert!(fork_id_recognized_by_either(&electra, root, &old, &new));
+        assert!(fork_id_recognized_by_either(&electra, root, &new, &old));
+        assert!(!fork_id_recognized_by_either(&electra, root, &old, &old));
+        let sepolia = fork_id_with_digest([0xd3, 0x1f, 0x61, 0x91]);
+        assert!(!fork_id_recognized_by_either(&sepolia, root, &old, &new));
+    }
+
+    #[test]
+    fn test_fork_within_known_schedule() {
+        let root = mainnet_genesis_validators_root();
+        // a node that only knows up to Deneb
+        let schedule = ForkSchedule::new(mainnet_fork_schedule().forks()[..5].to_vec());
+        let capella = fork_id_with_digest([0xbb, 0xa4, 0xda, 0x96]);
+        let deneb = fork_id_with_digest([0x6a, 0x95, 0xa1, 0xa9]);
+        let electra = fork_id_with_digest([0xad, 0x53, 0x2c, 0xeb]);
+        assert!(fork_within_known_schedule(&capella, root, &schedule));
+        assert!(fork_within_known_schedule(&deneb, root, &schedule));
+        assert!(!fork_within_known_schedule(&electra, root, &schedule));
+        assert!(fork_within_known_schedule(
+            &electra,
+            root,
+            &mainnet_fork_schedule()
+        ));
+    }
+}
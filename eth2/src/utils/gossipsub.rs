@@ -0,0 +1,54 @@
+use super::{compute_gossip_message_id, ForkName, GOSSIP_MAX_SIZE};
+use libp2p_gossipsub::{GossipsubConfig, GossipsubConfigBuilder, GossipsubMessage, MessageId};
+use std::time::Duration;
+
+fn message_id_for_fork(message: &GossipsubMessage, fork: ForkName) -> MessageId {
+    let topic = message
+        .topics
+        .first()
+        .map(|topic| topic.as_str())
+        .unwrap_or_default();
+    MessageId::from(hex::encode(compute_gossip_message_id(
+        topic,
+        &message.data,
+        fork,
+    )))
+}
+
+fn phase0_message_id(message: &GossipsubMessage) -> MessageId {
+    message_id_for_fork(message, ForkName::Phase0)
+}
+
+fn altair_message_id(message: &GossipsubMessage) -> MessageId {
+    message_id_for_fork(message, ForkName::Altair)
+}
+
+/// The gossipsub config recommended by the networking spec for the pinned phase0 spec.
+pub fn eth2_gossipsub_config() -> GossipsubConfig {
+    eth2_gossipsub_config_for_fork(ForkName::Phase0)
+}
+
+/// The gossipsub config recommended by the networking spec, with message ids computed as they are
+/// at `fork`.
+///
+/// The mesh parameters are D = 8, D_low = 6, D_high = 12 and D_lazy = 6, with a 700ms heartbeat,
+/// 6 heartbeats of history and gossip for the last 3 of them.
+pub fn eth2_gossipsub_config_for_fork(fork: ForkName) -> GossipsubConfig {
+    let message_id_fn: fn(&GossipsubMessage) -> MessageId = if fork >= ForkName::Altair {
+        altair_message_id
+    } else {
+        phase0_message_id
+    };
+    GossipsubConfigBuilder::new()
+        .max_transmit_size(GOSSIP_MAX_SIZE)
+        .mesh_n(8)
+        .mesh_n_low(6)
+        .mesh_n_high(12)
+        .gossip_lazy(6)
+        .heartbeat_interval(Duration::from_millis(700))
+        .fanout_ttl(Duration::from_secs(60))
+        .history_length(6)
+        .history_gossip(3)
+        .message_id_fn(message_id_fn)
+        .build()
+}
@@ -7,12 +7,39 @@ use crate::testnet::config::Eth2TestnetConfig;
 use crate::types::{ChainSpec, EnrForkId, EthSpec, Hash256, MainnetEthSpec, Slot};
 use libp2p_core::{identity::Keypair, identity::PublicKey, multiaddr::Protocol, Multiaddr, PeerId};
 #[cfg(not(feature = "local"))]
-use discv5::enr::{CombinedKey, CombinedPublicKey, Enr};
+use discv5::enr::{CombinedKey, CombinedPublicKey, Enr, EnrKey, EnrPublicKey};
 #[cfg(feature = "local")]
-use discv5_local::enr::{CombinedKey, CombinedPublicKey, Enr};
+use discv5_local::enr::{CombinedKey, CombinedPublicKey, Enr, EnrKey, EnrPublicKey};
 
+use std::net::{Ipv4Addr, Ipv6Addr};
 use std::path::PathBuf;
 
+mod archive;
+mod enr;
+mod filter;
+mod fork;
+#[cfg(feature = "gossipsub")]
+mod gossipsub;
+mod subnets;
+#[cfg(feature = "testing")]
+mod testing;
+mod testnet;
+mod time;
+mod topics;
+
+pub use archive::*;
+pub use enr::*;
+pub use filter::*;
+pub use fork::*;
+#[cfg(feature = "gossipsub")]
+pub use gossipsub::*;
+pub use subnets::*;
+#[cfg(feature = "testing")]
+pub use testing::*;
+pub use testnet::*;
+pub use time::*;
+pub use topics::*;
+
 pub fn load_testnet_config<E: EthSpec>(testnet_dir: PathBuf) -> Eth2TestnetConfig<E> {
     Eth2TestnetConfig::load(testnet_dir).unwrap()
 }
@@ -66,7 +93,7 @@ pub fn get_fork_id_from_dir(dir: Option<PathBuf>) -> Option<EnrForkId> {
     }
 }
 
-pub fn get_fork_id_from_enr(enr: &Enr<CombinedKey>) -> Option<EnrForkId> {
+pub fn get_fork_id_from_enr<K: EnrKey>(enr: &Enr<K>) -> Option<EnrForkId> {
     match enr.get("eth2") {
         Some(enr_fork_id) => match EnrForkId::from_ssz_bytes(enr_fork_id) {
             Ok(enr_fork_id) => Some(enr_fork_id),
@@ -76,7 +103,7 @@ pub fn get_fork_id_from_enr(enr: &Enr<CombinedKey>) -> Option<EnrForkId> {
     }
 }
 
-pub fn get_attnets_from_enr(enr: &Enr<CombinedKey>) -> Vec<u64> {
+pub fn get_attnets_from_enr<K: EnrKey>(enr: &Enr<K>) -> Vec<u64> {
     let mut attnets = vec![];
 
     if let Ok(bitfield) = get_bitfield_from_enr(enr) {
@@ -93,8 +120,8 @@ pub fn get_attnets_from_enr(enr: &Enr<CombinedKey>) -> Vec<u64> {
     return attnets;
 }
 
-pub fn get_bitfield_from_enr(
-    enr: &Enr<CombinedKey>,
+pub fn get_bitfield_from_enr<K: EnrKey>(
+    enr: &Enr<K>,
 ) -> Result<EnrBitfield<MainnetEthSpec>, &'static str> {
     let bitfield_bytes = enr
         .get("attnets")
@@ -119,7 +146,13 @@ pub fn get_fork_id_from_string(enr: String) -> Option<EnrForkId> {
 }
 
 pub fn create_topic_ids(enr_fork_id: EnrForkId) -> Vec<String> {
-    let network_config = NetworkConfig::default();
+    create_topic_ids_for_config(NetworkConfig::default(), enr_fork_id)
+}
+
+pub fn create_topic_ids_for_config(
+    network_config: NetworkConfig,
+    enr_fork_id: EnrForkId,
+) -> Vec<String> {
     let topic_kinds = network_config.topics; //type GossipKind
     let mut topic_ids: Vec<String> = vec![];
     for kind in topic_kinds {
@@ -142,6 +175,25 @@ pub fn get_gossip_topic_id(kind: GossipKind, enr_fork_id: EnrForkId) -> String {
 pub trait EnrExt {
     /// The libp2p `PeerId` for the record.
     fn peer_id(&self) -> PeerId;
+
+    /// The raw signature bytes of the record.
+    fn enr_signature_bytes(&self) -> Vec<u8>;
+
+    /// The compressed public key bytes of the record.
+    fn enr_public_key_bytes(&self) -> Vec<u8>;
+
+    /// The RLP encoded content covered by the signature, the sequence number followed by the
+    /// sorted key/value pairs.
+    fn enr_signing_content(&self) -> Vec<u8>;
+
+    /// The `ip` field as an address, or `None` if it is absent or not 4 bytes long.
+    fn enr_ipv4(&self) -> Option<Ipv4Addr>;
+
+    /// The `ip6` field as an address, or `None` if it is absent or not 16 bytes long.
+    fn enr_ipv6(&self) -> Option<Ipv6Addr>;
+
+    /// Every TCP and QUIC multiaddr of the record, each with the `/p2p/<peer_id>` suffix.
+    fn all_multiaddrs(&self) -> Vec<Multiaddr>;
 }
 
 /// Extend ENR CombinedPublicKey for libp2p types.
@@ -155,6 +207,64 @@ impl EnrExt for Enr<CombinedKey> {
     fn peer_id(&self) -> PeerId {
         self.public_key().into_peer_id()
     }
+
+    /// The raw signature bytes of the record.
+    fn enr_signature_bytes(&self) -> Vec<u8> {
+        self.signature().to_vec()
+    }
+
+    /// The compressed public key bytes of the record.
+    fn enr_public_key_bytes(&self) -> Vec<u8> {
+        self.public_key().encode()
+    }
+
+    /// The RLP encoded content covered by the signature, the sequence number followed by the
+    /// sorted key/value pairs.
+    fn enr_signing_content(&self) -> Vec<u8> {
+        let fields: Vec<_> = self.iter().collect();
+        let mut stream = rlp::RlpStream::new_list(fields.len() * 2 + 1);
+        stream.append(&self.seq());
+        for (key, value) in fields {
+            stream.append(key);
+            stream.append(value);
+        }
+        stream.out()
+    }
+
+    /// The `ip` field as an address, or `None` if it is absent or not 4 bytes long.
+    fn enr_ipv4(&self) -> Option<Ipv4Addr> {
+        match self.get("ip") {
+            Some(bytes) if bytes.len() == 4 => {
+                let mut octets = [0u8; 4];
+                octets.copy_from_slice(bytes);
+                Some(Ipv4Addr::from(octets))
+            }
+            _ => None,
+        }
+    }
+
+    /// The `ip6` field as an address, or `None` if it is absent or not 16 bytes long.
+    fn enr_ipv6(&self) -> Option<Ipv6Addr> {
+        match self.get("ip6") {
+            Some(bytes) if bytes.len() == 16 => {
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(bytes);
+                Some(Ipv6Addr::from(octets))
+            }
+            _ => None,
+        }
+    }
+
+    /// Every TCP and QUIC multiaddr of the record, each with the `/p2p/<peer_id>` suffix.
+    fn all_multiaddrs(&self) -> Vec<Multiaddr> {
+        let mut multiaddrs = tcp_multiaddrs(self);
+        for multiaddr in quic_multiaddrs(self) {
+            if !multiaddrs.contains(&multiaddr) {
+                multiaddrs.push(multiaddr);
+            }
+        }
+        multiaddrs
+    }
 }
 
 impl CombinedKeyPublicExt for CombinedPublicKey {
@@ -0,0 +1,370 @@
+use super::{get_attnets_from_enr, get_bitfield_from_enr, guess_client_from_enr, ClientKind};
+use crate::ssz::types::typenum::Unsigned;
+use crate::ssz::types::BitVector;
+use crate::ssz::Decode;
+use crate::types::{ChainSpec, Epoch, EthSpec, Slot, SubnetId};
+#[cfg(not(feature = "local"))]
+use discv5::enr::{CombinedKey, Enr, EnrKey, NodeId};
+#[cfg(feature = "local")]
+use discv5_local::enr::{CombinedKey, Enr, EnrKey, NodeId};
+use std::collections::HashMap;
+
+/// Number of sync committee subnets defined by the Altair spec.
+///
+/// The pinned `ChainSpec` predates Altair so this is not available from the spec itself.
+pub const SYNC_COMMITTEE_SUBNET_COUNT: u64 = 4;
+
+/// Number of validators in a sync committee on mainnet, defined by the Altair spec.
+pub const SYNC_COMMITTEE_SIZE: u64 = 512;
+
+/// Number of backbone attestation subnets each node subscribes to, defined by the Deneb spec.
+pub const SUBNETS_PER_NODE: u64 = 2;
+
+/// Number of epochs a node stays subscribed to its backbone subnets, defined by the Deneb spec.
+pub const EPOCHS_PER_SUBNET_SUBSCRIPTION: u64 = 256;
+
+/// Number of leading node id bits used to pick the backbone subnets, defined by the Deneb spec.
+pub const ATTESTATION_SUBNET_PREFIX_BITS: u32 = 6;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubnetValidationError {
+    /// The attestation subnet id is not below `SubnetBitfieldLength`.
+    AttestationSubnetOutOfRange(u64),
+    /// The sync committee subnet id is not below `SYNC_COMMITTEE_SUBNET_COUNT`.
+    SyncCommitteeSubnetOutOfRange(u64),
+}
+
+/// The SSZ length in bytes of the `attnets` bitfield, `BitVector<E::SubnetBitfieldLength>`.
+///
+/// Not `const` because trait bounds on `const fn` generics are not allowed.
+pub fn attnets_byte_length<E: EthSpec>() -> usize {
+    (E::SubnetBitfieldLength::to_usize() + 7) / 8
+}
+
+/// Returns true if the ENR has an `attnets` field of the expected length.
+pub fn attnets_length_valid<E: EthSpec, K: EnrKey>(enr: &Enr<K>) -> bool {
+    enr.get("attnets")
+        .map_or(false, |bytes| bytes.len() == attnets_byte_length::<E>())
+}
+
+/// Checks subnet ids against the spec bounds, returning the first one out of range.
+pub fn validate_subnet_ids<E: EthSpec>(
+    attnets: &[u64],
+    syncnets: &[u64],
+) -> Result<(), SubnetValidationError> {
+    let attestation_subnet_count = E::SubnetBitfieldLength::to_u64();
+    if let Some(subnet_id) = attnets.iter().find(|id| **id >= attestation_subnet_count) {
+        return Err(SubnetValidationError::AttestationSubnetOutOfRange(
+            *subnet_id,
+        ));
+    }
+    if let Some(subnet_id) = syncnets
+        .iter()
+        .find(|id| **id >= SYNC_COMMITTEE_SUBNET_COUNT)
+    {
+        return Err(SubnetValidationError::SyncCommitteeSubnetOutOfRange(
+            *subnet_id,
+        ));
+    }
+    Ok(())
+}
+
+/// The sync committee subnets for a participant at `index` in the sync committee.
+///
+/// Each subnet covers `SYNC_COMMITTEE_SIZE / SYNC_COMMITTEE_SUBNET_COUNT` consecutive indices,
+/// so a single index maps to one subnet. Indices outside of the committee map to none. The
+/// mapping depends only on these constants, so no `ChainSpec` is needed.
+pub fn sync_subnets_for_committee_index(index: u64) -> Vec<u64> {
+    if index >= SYNC_COMMITTEE_SIZE {
+        return vec![];
+    }
+    vec![index / (SYNC_COMMITTEE_SIZE / SYNC_COMMITTEE_SUBNET_COUNT)]
+}
+
+/// The attestation subnet for a committee, as defined by the spec's
+/// `compute_subnet_for_attestation`.
+///
+/// `E` supplies the slots per epoch, which the pinned `ChainSpec` does not hold.
+pub fn compute_subnet_for_attestation<E: EthSpec>(
+    slot: Slot,
+    committee_index: u64,
+    committees_per_slot: u64,
+    spec: &ChainSpec,
+) -> u64 {
+    let slots_since_epoch_start = slot.as_u64() % E::slots_per_epoch();
+    let committees_since_epoch_start = committees_per_slot * slots_since_epoch_start;
+    (committees_since_epoch_start + committee_index) % spec.attestation_subnet_count
+}
+
+/// The spec's swap-or-not `compute_shuffled_index`.
+fn compute_shuffled_index(mut index: u64, index_count: u64, seed: &[u8], rounds: u8) -> u64 {
+    for round in 0..rounds {
+        let mut pivot_preimage = seed.to_vec();
+        pivot_preimage.push(round);
+        let mut pivot_bytes = [0u8; 8];
+        pivot_bytes.copy_from_slice(&eth2_hashing::hash(&pivot_preimage)[..8]);
+        let pivot = u64::from_le_bytes(pivot_bytes) % index_count;
+
+        let flip = (pivot + index_count - index) % index_count;
+        let position = index.max(flip);
+        let mut source_preimage = pivot_preimage;
+        source_preimage.extend_from_slice(&((position / 256) as u32).to_le_bytes());
+        let source = eth2_hashing::hash(&source_preimage);
+        let byte = source[((position % 256) / 8) as usize];
+        if (byte >> (position % 8)) & 1 == 1 {
+            index = flip;
+        }
+    }
+    index
+}
+
+/// The attestation subnets a node is expected to subscribe to at `epoch`, as defined by the spec's
+/// `compute_subscribed_subnets`.
+pub fn backbone_subnets(node_id: &NodeId, epoch: Epoch, spec: &ChainSpec) -> Vec<u64> {
+    let raw = node_id.raw();
+    let node_id_prefix = u64::from(raw[0] >> (8 - ATTESTATION_SUBNET_PREFIX_BITS));
+    // the node id is a big endian uint256, so the low byte is enough for the modulus
+    let node_offset = u64::from(raw[31]) % EPOCHS_PER_SUBNET_SUBSCRIPTION;
+    let permutation_seed = eth2_hashing::hash(
+        &((epoch.as_u64() + node_offset) / EPOCHS_PER_SUBNET_SUBSCRIPTION).to_le_bytes(),
+    );
+    let permutated_prefix = compute_shuffled_index(
+        node_id_prefix,
+        1 << ATTESTATION_SUBNET_PREFIX_BITS,
+        &permutation_seed,
+        spec.shuffle_round_count,
+    );
+    (0..SUBNETS_PER_NODE)
+        .map(|index| (permutated_prefix + index) % spec.attestation_subnet_count)
+        .collect()
+}
+
+/// Returns true if every backbone subnet for the ENR's node id at `epoch` is set in its `attnets`.
+///
+/// ENRs without an `attnets` field never match. The backbone subnets rotate every
+/// `EPOCHS_PER_SUBNET_SUBSCRIPTION` epochs, so the answer depends on `epoch`.
+pub fn attnets_include_backbone(enr: &Enr<CombinedKey>, epoch: Epoch, spec: &ChainSpec) -> bool {
+    let bitfield = match get_bitfield_from_enr(enr) {
+        Ok(bitfield) => bitfield,
+        Err(_) => return false,
+    };
+    backbone_subnets(&enr.node_id(), epoch, spec)
+        .iter()
+        .all(|subnet_id| bitfield.get(*subnet_id as usize).unwrap_or(false))
+}
+
+/// The ENR's attestation subnets as `SubnetId`s, skipping any outside of the spec subnet count.
+pub fn get_subnet_ids_from_enr(enr: &Enr<CombinedKey>, spec: &ChainSpec) -> Vec<SubnetId> {
+    get_attnets_from_enr(enr)
+        .into_iter()
+        .filter(|subnet_id| *subnet_id < spec.attestation_subnet_count)
+        .map(SubnetId::new)
+        .collect()
+}
+
+/// The sync committee subnets set in the ENR's `syncnets` bitfield.
+///
+/// The field is an SSZ `Bitvector[SYNC_COMMITTEE_SUBNET_COUNT]`, a single byte with bit `i` set
+/// for subnet `i`. A missing or malformed field yields no subnets.
+pub fn get_syncnets_from_enr<K: EnrKey>(enr: &Enr<K>) -> Vec<u64> {
+    match enr.get("syncnets") {
+        Some(bytes) if bytes.len() == 1 => (0..SYNC_COMMITTEE_SUBNET_COUNT)
+            .filter(|i| bytes[0] & (1 << i) != 0)
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// The attestation subnets set in the ENR's `attnets` bitfield, decoded for the spec `E`.
+///
+/// Unlike `get_attnets_from_enr` this is not fixed to the mainnet bitfield length.
+pub fn get_attnets_for_spec<E: EthSpec, K: EnrKey>(enr: &Enr<K>) -> Vec<u64> {
+    enr.get("attnets")
+        .and_then(|bytes| BitVector::<E::SubnetBitfieldLength>::from_ssz_bytes(bytes).ok())
+        .map(|bitfield| {
+            (0..bitfield.len())
+                .filter(|i| bitfield.get(*i).unwrap_or(false))
+                .map(|i| i as u64)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The number of ENRs subscribed to each attestation subnet, indexed by subnet id.
+pub fn attnets_histogram<E: EthSpec>(enrs: &[Enr<CombinedKey>]) -> Vec<usize> {
+    let mut histogram = vec![0; E::SubnetBitfieldLength::to_usize()];
+    for enr in enrs {
+        for subnet_id in get_attnets_for_spec::<E, _>(enr) {
+            histogram[subnet_id as usize] += 1;
+        }
+    }
+    histogram
+}
+
+/// The number of attestation subnets set in the ENR's `attnets` bitfield.
+pub fn attnets_subscribed_count(enr: &Enr<CombinedKey>) -> usize {
+    get_attnets_from_enr(enr).len()
+}
+
+/// Per client, the number of ENRs and the total number of attestation subnets they subscribe to.
+pub fn attnets_by_client(enrs: &[Enr<CombinedKey>]) -> HashMap<ClientKind, (usize, usize)> {
+    let mut stats = HashMap::new();
+    for enr in enrs {
+        let (nodes, subnets) = stats.entry(guess_client_from_enr(enr)).or_insert((0, 0));
+        *nodes += 1;
+        *subnets += attnets_subscribed_count(enr);
+    }
+    stats
+}
+
+/// The attestation subnets with fewer than `min_peers` subscribed ENRs.
+pub fn subnet_coverage_gaps<E: EthSpec>(enrs: &[Enr<CombinedKey>], min_peers: usize) -> Vec<u64> {
+    attnets_histogram::<E>(enrs)
+        .into_iter()
+        .enumerate()
+        .filter(|(_, count)| *count < min_peers)
+        .map(|(subnet_id, _)| subnet_id as u64)
+        .collect()
+}
+
+/// Estimates the fewest candidates needed for every attestation subnet to have a subscriber.
+///
+/// This is a greedy set cover approximation, repeatedly taking the ENR that covers the most
+/// uncovered subnets, so the count can exceed the optimum. Returns `None` if the candidates
+/// together do not cover every subnet.
+pub fn min_peers_for_coverage<E: EthSpec>(candidate_enrs: &[Enr<CombinedKey>]) -> Option<usize> {
+    let mut uncovered: Vec<u64> = (0..E::SubnetBitfieldLength::to_u64()).collect();
+    let mut candidates: Vec<Vec<u64>> = candidate_enrs
+        .iter()
+        .map(|enr| get_attnets_for_spec::<E, _>(enr))
+        .collect();
+    let mut peers = 0;
+    while !uncovered.is_empty() {
+        let (best, newly_covered) = candidates
+            .iter()
+            .enumerate()
+            .map(|(i, attnets)| {
+                let newly_covered = attnets.iter().filter(|id| uncovered.contains(id)).count();
+                (i, newly_covered)
+            })
+            .max_by_key(|(_, newly_covered)| *newly_covered)?;
+        if newly_covered == 0 {
+            return None;
+        }
+        let attnets = candidates.swap_remove(best);
+        uncovered.retain(|subnet_id| !attnets.contains(subnet_id));
+        peers += 1;
+    }
+    Some(peers)
+}
+
+/// The number of attestation subnets both ENRs subscribe to, 0 if either has no `attnets` field.
+pub fn subnet_overlap<E: EthSpec>(a: &Enr<CombinedKey>, b: &Enr<CombinedKey>) -> usize {
+    let decode = |enr: &Enr<CombinedKey>| {
+        enr.get("attnets")
+            .and_then(|bytes| BitVector::<E::SubnetBitfieldLength>::from_ssz_bytes(bytes).ok())
+    };
+    match (decode(a), decode(b)) {
+        (Some(a), Some(b)) => a.intersection(&b).num_set_bits(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MainnetEthSpec;
+
+    #[test]
+    fn test_sync_subnets_for_committee_index() {
+        // subnets are contiguous blocks of SYNC_COMMITTEE_SIZE / SYNC_COMMITTEE_SUBNET_COUNT
+        assert_eq!(sync_subnets_for_committee_index(0), vec![0]);
+        assert_eq!(sync_subnets_for_committee_index(127), vec![0]);
+        assert_eq!(sync_subnets_for_committee_index(128), vec![1]);
+        assert_eq!(sync_subnets_for_committee_index(300), vec![2]);
+        assert_eq!(sync_subnets_for_committee_index(511), vec![3]);
+        assert_eq!(sync_subnets_for_committee_index(512), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn test_compute_subnet_for_attestation() {
+        let spec = ChainSpec::mainnet();
+        let subnet = |slot, committee_index, committees_per_slot| {
+            compute_subnet_for_attestation::<MainnetEthSpec>(
+                Slot::new(slot),
+                committee_index,
+                committees_per_slot,
+                &spec,
+            )
+        };
+        assert_eq!(subnet(0, 0, 1), 0);
+        assert_eq!(subnet(35, 2, 4), 14);
+        // the slot within the epoch is what matters
+        assert_eq!(subnet(3, 2, 4), subnet(35, 2, 4));
+        assert_eq!(subnet(31, 5, 64), 5);
+        assert_eq!(subnet(1, 63, 64), 63);
+    }
+
+    #[test]
+    fn test_compute_shuffled_index() {
+        // expected values computed with the pyspec `compute_shuffled_index` and the mainnet 90
+        // rounds
+        let seed = [0u8; 32];
+        let shuffled: Vec<u64> = (0..10)
+            .map(|index| compute_shuffled_index(index, 100, &seed, 90))
+            .collect();
+        assert_eq!(shuffled, vec![79, 25, 97, 2, 29, 3, 4, 80, 18, 63]);
+
+        let seed = eth2_hashing::hash(b"imp");
+        let shuffled: Vec<u64> = [0, 1, 500, 999]
+            .iter()
+            .map(|index| compute_shuffled_index(*index, 1000, &seed, 90))
+            .collect();
+        assert_eq!(shuffled, vec![14, 70, 231, 591]);
+
+        // the shuffle is a permutation
+        let mut all: Vec<u64> = (0..100)
+            .map(|index| compute_shuffled_index(index, 100, &[0u8; 32], 90))
+            .collect();
+        all.sort();
+        assert_eq!(all, (0..100).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn test_backbone_subnets() {
+        // expected values computed with the pyspec `compute_subscribed_subnets`
+        let spec = ChainSpec::mainnet();
+        let mut raw = [0u8; 32];
+        for (i, byte) in raw.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        let node_id = NodeId::new(&raw);
+        assert_eq!(
+            backbone_subnets(&node_id, Epoch::new(0), &spec),
+            vec![49, 50]
+        );
+        // the node offset of 31 moves the rotation from epoch 256 to 225
+        assert_eq!(
+            backbone_subnets(&node_id, Epoch::new(224), &spec),
+            vec![49, 50]
+        );
+        assert_eq!(
+            backbone_subnets(&node_id, Epoch::new(225), &spec),
+            vec![16, 17]
+        );
+        assert_eq!(
+            backbone_subnets(&node_id, Epoch::new(100_000), &spec),
+            vec![35, 36]
+        );
+
+        let node_id = NodeId::new(&[0xff; 32]);
+        assert_eq!(
+            backbone_subnets(&node_id, Epoch::new(0), &spec),
+            vec![57, 58]
+        );
+        assert_eq!(
+            backbone_subnets(&node_id, Epoch::new(300_000), &spec),
+            vec![7, 8]
+        );
+    }
+}
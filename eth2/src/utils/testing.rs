@@ -0,0 +1,94 @@
+use crate::ssz::Encode;
+use crate::types::EnrForkId;
+#[cfg(not(feature = "local"))]
+use discv5::enr::{CombinedKey, Enr, EnrBuilder};
+#[cfg(feature = "local")]
+use discv5_local::enr::{CombinedKey, Enr, EnrBuilder};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::net::{IpAddr, Ipv4Addr};
+
+fn random_key(rng: &mut StdRng) -> CombinedKey {
+    loop {
+        let mut secret = rng.gen::<[u8; 32]>();
+        // a few byte strings are not valid secret keys, just draw again
+        if let Ok(key) = CombinedKey::secp256k1_from_bytes(&mut secret) {
+            return key;
+        }
+    }
+}
+
+/// An ENR listening on localhost with the same TCP and UDP port, for tests.
+pub fn local_test_enr(key: &CombinedKey, port: u16) -> Enr<CombinedKey> {
+    EnrBuilder::new("v4")
+        .ip(IpAddr::V4(Ipv4Addr::LOCALHOST))
+        .tcp(port)
+        .udp(port)
+        .build(key)
+        .expect("valid local test enr")
+}
+
+/// Mints `count` signed ENRs with random IPs, ports, fork ids and attnets.
+///
+/// The same seed always produces the same corpus.
+pub fn generate_enr_corpus(count: usize, seed: u64) -> Vec<Enr<CombinedKey>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| {
+            let key = random_key(&mut rng);
+            let enr_fork_id = EnrForkId {
+                fork_digest: rng.gen(),
+                next_fork_version: rng.gen(),
+                next_fork_epoch: u64::MAX.into(),
+            };
+            let port = rng.gen_range(1024, u16::MAX);
+            EnrBuilder::new("v4")
+                .ip(IpAddr::V4(Ipv4Addr::from(rng.gen::<u32>())))
+                .tcp(port)
+                .udp(port)
+                .add_value("eth2".into(), enr_fork_id.as_ssz_bytes())
+                .add_value("attnets".into(), rng.gen::<[u8; 8]>().to_vec())
+                .build(&key)
+                .expect("valid corpus enr")
+        })
+        .collect()
+}
+
+/// Signs one ENR per fork id for the same key, with sequence numbers counting up from 1.
+///
+/// Models a node updating its record across fork transitions.
+pub fn simulate_fork_transition(
+    key: &CombinedKey,
+    ip: IpAddr,
+    forks: &[EnrForkId],
+) -> Vec<Enr<CombinedKey>> {
+    forks
+        .iter()
+        .enumerate()
+        .map(|(i, enr_fork_id)| {
+            EnrBuilder::new("v4")
+                .seq(i as u64 + 1)
+                .ip(ip)
+                .add_value("eth2".into(), enr_fork_id.as_ssz_bytes())
+                .build(key)
+                .expect("valid fork transition enr")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_base64(enrs: &[Enr<CombinedKey>]) -> Vec<String> {
+        enrs.iter().map(|enr| enr.to_base64()).collect()
+    }
+
+    #[test]
+    fn test_generate_enr_corpus_deterministic() {
+        let corpus = to_base64(&generate_enr_corpus(20, 7));
+        assert_eq!(corpus.len(), 20);
+        assert_eq!(to_base64(&generate_enr_corpus(20, 7)), corpus);
+        assert_ne!(to_base64(&generate_enr_corpus(20, 8)), corpus);
+    }
+}
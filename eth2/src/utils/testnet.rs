@@ -0,0 +1,387 @@
+use super::{parse_enr, EnrParseError};
+use crate::testnet::config::Eth2TestnetConfig;
+use crate::types::{ChainSpec, EthSpec};
+#[cfg(not(feature = "local"))]
+use discv5::enr::{CombinedKey, Enr};
+#[cfg(feature = "local")]
+use discv5_local::enr::{CombinedKey, Enr};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::Duration;
+
+pub const YAML_CONFIG_FILE: &str = "config.yaml";
+pub const GENESIS_STATE_FILE: &str = "genesis.ssz";
+pub const BOOT_ENR_FILE: &str = "boot_enr.yaml";
+pub const DEPLOY_BLOCK_FILE: &str = "deploy_block.txt";
+
+pub const TESTNET_DIR_FILES: [&str; 4] = [
+    YAML_CONFIG_FILE,
+    GENESIS_STATE_FILE,
+    BOOT_ENR_FILE,
+    DEPLOY_BLOCK_FILE,
+];
+
+#[derive(Debug)]
+pub enum LoadConfigError {
+    /// The testnet directory or one of its files could not be read. Worth retrying.
+    Io(io::Error),
+    /// The files were read but their contents could not be decoded. Not worth retrying.
+    Decode(String),
+}
+
+#[derive(Debug)]
+pub enum BootnodeError {
+    Io(io::Error),
+    /// The file is not a YAML sequence of strings.
+    Yaml(serde_yaml::Error),
+    /// Entries that could not be parsed, with their 0-based positions in the sequence.
+    InvalidEntries(Vec<(usize, EnrParseError)>),
+}
+
+/// Opens each of the `TESTNET_DIR_FILES` that exists and reads its metadata, so IO failures
+/// surface before decoding is attempted without reading the files twice. Missing files are left
+/// for `Eth2TestnetConfig::load` to reject if it needs them.
+fn probe_testnet_dir(dir: &Path) -> io::Result<()> {
+    fs::read_dir(dir)?;
+    for file in TESTNET_DIR_FILES.iter() {
+        match fs::File::open(dir.join(file)).and_then(|file| file.metadata()) {
+            Ok(_) => (),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Loads the testnet config, retrying IO failures up to `attempts` times.
+///
+/// The delay starts at `backoff` and doubles after each failed attempt. Decode errors are
+/// returned immediately.
+///
+/// Only failures while probing the files are retried. `Eth2TestnetConfig::load` reads them again
+/// and reports every failure as a string, so an IO error it hits after a successful probe is
+/// returned as `Decode` and not retried.
+pub fn load_testnet_config_with_retry<E: EthSpec>(
+    dir: PathBuf,
+    attempts: usize,
+    backoff: Duration,
+) -> Result<Eth2TestnetConfig<E>, LoadConfigError> {
+    let mut delay = backoff;
+    let mut attempt = 1;
+    loop {
+        match probe_testnet_dir(&dir) {
+            Ok(()) => return Eth2TestnetConfig::load(dir).map_err(LoadConfigError::Decode),
+            Err(e) if attempt >= attempts => return Err(LoadConfigError::Io(e)),
+            Err(_) => {
+                sleep(delay);
+                delay = delay.checked_mul(2).unwrap_or(delay);
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Writes the ENRs as a YAML sequence of base64 strings, the `boot_enr.yaml` format.
+pub fn write_bootnodes_yaml<W: Write>(writer: &mut W, enrs: &[Enr<CombinedKey>]) -> io::Result<()> {
+    for enr in enrs {
+        writeln!(writer, "- \"{}\"", enr.to_base64())?;
+    }
+    writer.flush()
+}
+
+/// Reads a YAML sequence of base64 ENRs, as written by `write_bootnodes_yaml`.
+///
+/// An empty document is an empty list. Every invalid entry is reported, not just the first.
+pub fn read_bootnodes_yaml<R: Read>(mut reader: R) -> Result<Vec<Enr<CombinedKey>>, BootnodeError> {
+    let mut contents = String::new();
+    reader
+        .read_to_string(&mut contents)
+        .map_err(BootnodeError::Io)?;
+    let entries: Option<Vec<String>> = if contents.trim().is_empty() {
+        None
+    } else {
+        serde_yaml::from_str(&contents).map_err(BootnodeError::Yaml)?
+    };
+
+    let mut enrs = vec![];
+    let mut invalid_entries = vec![];
+    for (i, entry) in entries.unwrap_or_default().iter().enumerate() {
+        match parse_enr(entry) {
+            Ok(enr) => enrs.push(enr),
+            Err(e) => invalid_entries.push((i, e)),
+        }
+    }
+
+    if invalid_entries.is_empty() {
+        Ok(enrs)
+    } else {
+        Err(BootnodeError::InvalidEntries(invalid_entries))
+    }
+}
+
+/// Checks the testnet dir has all of the `TESTNET_DIR_FILES`, without parsing any of them.
+///
+/// Returns the names of any missing files.
+pub fn validate_testnet_dir(dir: &Path) -> Result<(), Vec<String>> {
+    if !dir.is_dir() {
+        return Err(vec![dir.display().to_string()]);
+    }
+    let missing: Vec<String> = TESTNET_DIR_FILES
+        .iter()
+        .filter(|file| !dir.join(file).is_file())
+        .map(|file| file.to_string())
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(missing)
+    }
+}
+
+/// Reads the ENRs in the testnet dir's `boot_enr.yaml`.
+pub fn load_boot_enrs(dir: &Path) -> Result<Vec<Enr<CombinedKey>>, BootnodeError> {
+    let file = fs::File::open(dir.join(BOOT_ENR_FILE)).map_err(BootnodeError::Io)?;
+    read_bootnodes_yaml(file)
+}
+
+/// Reads the eth1 block number the deposit contract was deployed at from `deploy_block.txt`.
+pub fn load_deploy_block(dir: &Path) -> Result<u64, LoadConfigError> {
+    let contents = fs::read_to_string(dir.join(DEPLOY_BLOCK_FILE)).map_err(LoadConfigError::Io)?;
+    contents.trim().parse::<u64>().map_err(|e| {
+        LoadConfigError::Decode(format!("Unable to parse {}: {}", DEPLOY_BLOCK_FILE, e))
+    })
+}
+
+/// A spec constant that differs between two `ChainSpec`s, with both values debug formatted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpecFieldDiff {
+    pub field: &'static str,
+    pub a: String,
+    pub b: String,
+}
+
+macro_rules! diff_spec_fields {
+    ($a:expr, $b:expr, $diffs:expr, $($field:ident),+) => {
+        $(
+            if $a.$field != $b.$field {
+                $diffs.push(SpecFieldDiff {
+                    field: stringify!($field),
+                    a: format!("{:?}", $a.$field),
+                    b: format!("{:?}", $b.$field),
+                });
+            }
+        )+
+    };
+}
+
+/// Lists the spec constants that differ between `a` and `b`.
+///
+/// Slots per epoch is part of the `EthSpec` rather than the `ChainSpec`, so it is not compared.
+pub fn diff_chain_specs(a: &ChainSpec, b: &ChainSpec) -> Vec<SpecFieldDiff> {
+    let mut diffs = vec![];
+    diff_spec_fields!(
+        a,
+        b,
+        diffs,
+        genesis_slot,
+        far_future_epoch,
+        genesis_fork_version,
+        min_genesis_time,
+        min_genesis_active_validator_count,
+        milliseconds_per_slot,
+        max_committees_per_slot,
+        target_committee_size,
+        shuffle_round_count,
+        max_effective_balance,
+        ejection_balance,
+        min_attestation_inclusion_delay,
+        eth1_follow_distance,
+        attestation_subnet_count,
+        target_aggregators_per_committee,
+        random_subnets_per_validator,
+        epochs_per_random_subnet_subscription
+    );
+    diffs
+}
+
+/// A field that two testnet configs set to incompatible values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeConflict {
+    pub field: &'static str,
+}
+
+/// Applies the fields set in `overlay` onto `base`.
+///
+/// `boot_enr` and `yaml_config` are mergeable, the overlay replaces them only if it sets them.
+/// The deposit contract address and deploy block always come from the overlay. `genesis_state`
+/// is exclusive: it is taken from whichever config sets it, and it is a conflict for both to set
+/// different states.
+pub fn merge_testnet_configs<E: EthSpec>(
+    base: Eth2TestnetConfig<E>,
+    overlay: Eth2TestnetConfig<E>,
+) -> Result<Eth2TestnetConfig<E>, MergeConflict> {
+    let genesis_state = match (base.genesis_state, overlay.genesis_state) {
+        (Some(a), Some(b)) if a != b => {
+            return Err(MergeConflict {
+                field: "genesis_state",
+            })
+        }
+        (a, b) => b.or(a),
+    };
+    Ok(Eth2TestnetConfig {
+        deposit_contract_address: overlay.deposit_contract_address,
+        deposit_contract_deploy_block: overlay.deposit_contract_deploy_block,
+        boot_enr: overlay.boot_enr.or(base.boot_enr),
+        genesis_state,
+        yaml_config: overlay.yaml_config.or(base.yaml_config),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MainnetEthSpec;
+    #[cfg(not(feature = "local"))]
+    use discv5::enr::EnrBuilder;
+    #[cfg(feature = "local")]
+    use discv5_local::enr::EnrBuilder;
+
+    fn boot_enrs(count: u16) -> Vec<Enr<CombinedKey>> {
+        (0..count)
+            .map(|i| {
+                EnrBuilder::new("v4")
+                    .ip("10.0.0.1".parse().unwrap())
+                    .udp(9000 + i)
+                    .build(&CombinedKey::generate_secp256k1())
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    fn to_base64(enrs: &[Enr<CombinedKey>]) -> Vec<String> {
+        enrs.iter().map(|enr| enr.to_base64()).collect()
+    }
+
+    #[test]
+    fn test_bootnodes_yaml_round_trip() {
+        for enrs in vec![vec![], boot_enrs(3)] {
+            let mut yaml = vec![];
+            write_bootnodes_yaml(&mut yaml, &enrs).unwrap();
+            let read = read_bootnodes_yaml(&yaml[..]).unwrap();
+            assert_eq!(to_base64(&read), to_base64(&enrs));
+        }
+    }
+
+    #[test]
+    fn test_read_bootnodes_yaml_document_marker_and_comments() {
+        let enrs = boot_enrs(2);
+        let yaml = format!(
+            "---\n# boot nodes\n- {} # first\n- \"{}\"\n",
+            enrs[0].to_base64(),
+            enrs[1].to_base64()
+        );
+        let read = read_bootnodes_yaml(yaml.as_bytes()).unwrap();
+        assert_eq!(to_base64(&read), to_base64(&enrs));
+    }
+
+    #[test]
+    fn test_read_bootnodes_yaml_invalid() {
+        let enr = boot_enrs(1).remove(0);
+        let yaml = format!("- not-an-enr\n- {}\n- enr:-AAAA\n", enr.to_base64());
+        match read_bootnodes_yaml(yaml.as_bytes()) {
+            Err(BootnodeError::InvalidEntries(entries)) => {
+                let positions: Vec<usize> = entries.iter().map(|(i, _)| *i).collect();
+                assert_eq!(positions, vec![0, 2]);
+            }
+            other => panic!("expected invalid entries, got {:?}", other.map(|_| ())),
+        }
+        match read_bootnodes_yaml(&b"boot: nodes"[..]) {
+            Err(BootnodeError::Yaml(_)) => (),
+            other => panic!("expected a yaml error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("imp-testnet-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_load_boot_enrs() {
+        let dir = scratch_dir("boot-enrs");
+        fs::create_dir_all(&dir).unwrap();
+        let enrs = boot_enrs(2);
+        let yaml = format!(
+            "---\n- {}\n- {}\n",
+            enrs[0].to_base64(),
+            enrs[1].to_base64()
+        );
+        fs::write(dir.join(BOOT_ENR_FILE), yaml).unwrap();
+        let loaded = load_boot_enrs(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(to_base64(&loaded.unwrap()), to_base64(&enrs));
+        match load_boot_enrs(&dir) {
+            Err(BootnodeError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::NotFound),
+            other => panic!("expected an IO error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_load_testnet_config_missing_dir() {
+        let dir = scratch_dir("missing");
+        let start = std::time::Instant::now();
+        let result =
+            load_testnet_config_with_retry::<MainnetEthSpec>(dir, 3, Duration::from_millis(20));
+        // two retries, after 20ms and 40ms
+        assert!(start.elapsed() >= Duration::from_millis(60));
+        match result {
+            Err(LoadConfigError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::NotFound),
+            other => panic!("expected an IO error, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_load_testnet_config_retries_until_dir_appears() {
+        let dir = scratch_dir("appears");
+        let writer_dir = dir.clone();
+        let writer = std::thread::spawn(move || {
+            sleep(Duration::from_millis(50));
+            fs::create_dir_all(&writer_dir).unwrap();
+            fs::write(writer_dir.join(YAML_CONFIG_FILE), "{ not: [valid yaml").unwrap();
+            fs::write(writer_dir.join(DEPLOY_BLOCK_FILE), "0").unwrap();
+        });
+        let result = load_testnet_config_with_retry::<MainnetEthSpec>(
+            dir.clone(),
+            6,
+            Duration::from_millis(100),
+        );
+        writer.join().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        // a decode error means a retry got past the missing dir
+        match result {
+            Err(LoadConfigError::Decode(_)) => (),
+            other => panic!("expected a decode error, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_load_testnet_config_malformed_config() {
+        let dir = scratch_dir("malformed");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(YAML_CONFIG_FILE), "{ not: [valid yaml").unwrap();
+        fs::write(dir.join(DEPLOY_BLOCK_FILE), "0").unwrap();
+        let result = load_testnet_config_with_retry::<MainnetEthSpec>(
+            dir.clone(),
+            3,
+            Duration::from_millis(0),
+        );
+        fs::remove_dir_all(&dir).unwrap();
+        match result {
+            Err(LoadConfigError::Decode(_)) => (),
+            other => panic!("expected a decode error, got {:?}", other.err()),
+        }
+    }
+}
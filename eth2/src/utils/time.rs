@@ -0,0 +1,25 @@
+use crate::types::{ChainSpec, Epoch, EthSpec, Slot};
+
+/// The slot at unix time `now` in seconds, or slot 0 before genesis.
+pub fn current_slot(genesis_time: u64, now: u64, spec: &ChainSpec) -> Slot {
+    let millis_since_genesis = now.saturating_sub(genesis_time).saturating_mul(1000);
+    Slot::new(millis_since_genesis / spec.milliseconds_per_slot)
+}
+
+/// The epoch at unix time `now` in seconds, or epoch 0 before genesis.
+///
+/// `E` supplies the slots per epoch, which the pinned `ChainSpec` does not hold.
+pub fn current_epoch<E: EthSpec>(genesis_time: u64, now: u64, spec: &ChainSpec) -> Epoch {
+    current_slot(genesis_time, now, spec).epoch(E::slots_per_epoch())
+}
+
+/// The unix time in seconds at which `slot` starts, the inverse of `current_slot`.
+///
+/// Saturates at `u64::MAX` rather than overflowing for far future slots.
+pub fn slot_to_timestamp(slot: Slot, genesis_time: u64, spec: &ChainSpec) -> u64 {
+    let seconds = u128::from(slot.as_u64()) * u128::from(spec.milliseconds_per_slot) / 1000;
+    if seconds > u128::from(u64::MAX) {
+        return u64::MAX;
+    }
+    genesis_time.saturating_add(seconds as u64)
+}
@@ -0,0 +1,744 @@
+use super::{
+    backbone_subnets, create_topic_ids, get_attnets_for_spec, get_attnets_from_enr,
+    get_fork_id_from_enr, get_gossip_topic_id, get_syncnets_from_enr, scheduled_fork_ids, ForkName,
+    ForkSchedule, SYNC_COMMITTEE_SUBNET_COUNT,
+};
+use crate::libp2p::types::{GossipEncoding, GossipKind, GossipTopic};
+use crate::libp2p::{NetworkConfig, Topic, TopicHash};
+use crate::types::{ChainSpec, EnrForkId, Epoch, EthSpec, Hash256, SubnetId};
+#[cfg(not(feature = "local"))]
+use discv5::enr::{CombinedKey, Enr, NodeId};
+#[cfg(feature = "local")]
+use discv5_local::enr::{CombinedKey, Enr, NodeId};
+use std::collections::BTreeSet;
+
+pub const TOPIC_PREFIX: &str = "eth2";
+pub const SSZ_SNAPPY_ENCODING_POSTFIX: &str = "ssz_snappy";
+pub const BEACON_ATTESTATION_PREFIX: &str = "beacon_attestation_";
+pub const SYNC_COMMITTEE_PREFIX_TOPIC: &str = "sync_committee_";
+pub const BLOB_SIDECAR_PREFIX: &str = "blob_sidecar_";
+
+/// Topic name prefixes of the kinds that are indexed by subnet.
+pub const SUBNET_TOPIC_PREFIXES: [&str; 3] = [
+    BEACON_ATTESTATION_PREFIX,
+    SYNC_COMMITTEE_PREFIX_TOPIC,
+    BLOB_SIDECAR_PREFIX,
+];
+
+/// Number of blob sidecar subnets defined by the Deneb spec.
+pub const BLOB_SIDECAR_SUBNET_COUNT: u64 = 6;
+
+/// Number of blob sidecar subnets from Electra.
+pub const BLOB_SIDECAR_SUBNET_COUNT_ELECTRA: u64 = 9;
+
+/// Number of data column sidecar subnets, which replace the blob sidecar subnets from Fulu.
+pub const DATA_COLUMN_SIDECAR_SUBNET_COUNT: u64 = 128;
+
+/// The maximum size of a gossip message, `GOSSIP_MAX_SIZE` in the phase0 networking spec.
+pub const GOSSIP_MAX_SIZE: usize = 1 << 20;
+
+pub const MESSAGE_DOMAIN_INVALID_SNAPPY: [u8; 4] = [0, 0, 0, 0];
+pub const MESSAGE_DOMAIN_VALID_SNAPPY: [u8; 4] = [1, 0, 0, 0];
+
+/// The components of a `/eth2/<fork_digest>/<name>/<encoding>` topic string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedTopic {
+    pub fork_digest: [u8; 4],
+    pub name: String,
+    pub encoding: String,
+}
+
+pub fn attestation_subnet_topic_id(subnet_id: u64, enr_fork_id: EnrForkId) -> String {
+    get_gossip_topic_id(
+        GossipKind::Attestation(SubnetId::new(subnet_id)),
+        enr_fork_id,
+    )
+}
+
+/// `GossipKind` has no sync committee variant, so the topic is formatted directly.
+pub fn sync_committee_subnet_topic_id(subnet_id: u64, enr_fork_id: EnrForkId) -> String {
+    format!(
+        "/{}/{}/{}{}/{}",
+        TOPIC_PREFIX,
+        hex::encode(enr_fork_id.fork_digest),
+        SYNC_COMMITTEE_PREFIX_TOPIC,
+        subnet_id,
+        SSZ_SNAPPY_ENCODING_POSTFIX
+    )
+}
+
+/// Like `get_gossip_topic_id`, for when only the fork digest is known.
+///
+/// The other `EnrForkId` fields are not part of the topic string.
+pub fn topic_id_from_digest(kind: GossipKind, fork_digest: [u8; 4]) -> String {
+    GossipTopic::new(kind, GossipEncoding::default(), fork_digest).into()
+}
+
+/// Like `create_topic_ids`, for when only the fork digest is known.
+pub fn topic_ids_from_digest(fork_digest: [u8; 4]) -> Vec<String> {
+    NetworkConfig::default()
+        .topics
+        .into_iter()
+        .map(|kind| topic_id_from_digest(kind, fork_digest))
+        .collect()
+}
+
+/// Checks that any subnet index carried by the kind is below the spec subnet count.
+pub fn validate_gossip_kind(kind: &GossipKind, spec: &ChainSpec) -> Result<(), &'static str> {
+    match kind {
+        GossipKind::Attestation(subnet_id) if **subnet_id >= spec.attestation_subnet_count => {
+            Err("attestation subnet id is out of range")
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Like `get_gossip_topic_id`, but rejects kinds that fail `validate_gossip_kind`.
+pub fn get_gossip_topic_id_checked(
+    kind: GossipKind,
+    enr_fork_id: EnrForkId,
+    spec: &ChainSpec,
+) -> Result<String, &'static str> {
+    validate_gossip_kind(&kind, spec)?;
+    Ok(get_gossip_topic_id(kind, enr_fork_id))
+}
+
+/// A human readable name for the kind, like "Beacon Blocks" or "Attestation Subnet 5".
+pub fn gossip_kind_label(kind: &GossipKind) -> String {
+    match kind {
+        GossipKind::BeaconBlock => "Beacon Blocks".to_string(),
+        GossipKind::BeaconAggregateAndProof => "Aggregate And Proofs".to_string(),
+        GossipKind::Attestation(subnet_id) => format!("Attestation Subnet {}", **subnet_id),
+        GossipKind::VoluntaryExit => "Voluntary Exits".to_string(),
+        GossipKind::ProposerSlashing => "Proposer Slashings".to_string(),
+        GossipKind::AttesterSlashing => "Attester Slashings".to_string(),
+    }
+}
+
+/// Code of the first attestation subnet kind. The subnet id is stored in the low 8 bits.
+pub const ATTESTATION_KIND_CODE: u16 = 0x100;
+
+/// A stable integer code for the kind, for use as a metrics label.
+///
+/// The global kinds are 0 to 4 in the order beacon block, aggregate and proof, voluntary exit,
+/// proposer slashing and attester slashing. Attestation subnets are `ATTESTATION_KIND_CODE` plus
+/// the subnet id.
+///
+/// Only subnet ids 0 to 255 have a code of their own. Larger ids keep their low 8 bits and so share
+/// a code with a lower subnet. They are never valid for the spec's subnet count, so check kinds
+/// from untrusted input with `validate_gossip_kind` first.
+pub fn gossip_kind_code(kind: &GossipKind) -> u16 {
+    match kind {
+        GossipKind::BeaconBlock => 0,
+        GossipKind::BeaconAggregateAndProof => 1,
+        GossipKind::VoluntaryExit => 2,
+        GossipKind::ProposerSlashing => 3,
+        GossipKind::AttesterSlashing => 4,
+        GossipKind::Attestation(subnet_id) => ATTESTATION_KIND_CODE | (**subnet_id as u16 & 0xff),
+    }
+}
+
+/// The kind for a code from `gossip_kind_code`, or `None` if the code is unassigned.
+pub fn gossip_kind_from_code(code: u16) -> Option<GossipKind> {
+    match code {
+        0 => Some(GossipKind::BeaconBlock),
+        1 => Some(GossipKind::BeaconAggregateAndProof),
+        2 => Some(GossipKind::VoluntaryExit),
+        3 => Some(GossipKind::ProposerSlashing),
+        4 => Some(GossipKind::AttesterSlashing),
+        code if code & 0xff00 == ATTESTATION_KIND_CODE => Some(GossipKind::Attestation(
+            SubnetId::new(u64::from(code & 0xff)),
+        )),
+        _ => None,
+    }
+}
+
+/// The default topics paired with the label of their kind.
+pub fn labeled_topic_ids(enr_fork_id: EnrForkId) -> Vec<(String, String)> {
+    NetworkConfig::default()
+        .topics
+        .into_iter()
+        .map(|kind| {
+            let label = gossip_kind_label(&kind);
+            (get_gossip_topic_id(kind, enr_fork_id.clone()), label)
+        })
+        .collect()
+}
+
+/// The proposer slashing, attester slashing and voluntary exit topics, in that order.
+pub fn slashing_topic_ids(enr_fork_id: EnrForkId) -> Vec<String> {
+    vec![
+        GossipKind::ProposerSlashing,
+        GossipKind::AttesterSlashing,
+        GossipKind::VoluntaryExit,
+    ]
+    .into_iter()
+    .map(|kind| get_gossip_topic_id(kind, enr_fork_id.clone()))
+    .collect()
+}
+
+/// The names of the global, non subnet, topics active at `fork`.
+pub fn global_topic_names(fork: ForkName) -> Vec<&'static str> {
+    let mut names = vec![
+        "beacon_block",
+        "beacon_aggregate_and_proof",
+        "voluntary_exit",
+        "proposer_slashing",
+        "attester_slashing",
+    ];
+    if fork >= ForkName::Altair {
+        names.push("sync_committee_contribution_and_proof");
+    }
+    if fork >= ForkName::Capella {
+        names.push("bls_to_execution_change");
+    }
+    names
+}
+
+/// The number of topics a node subscribed to everything at `fork` should have.
+///
+/// This is the global topics plus one topic per attestation subnet, per sync committee subnet
+/// from Altair and per blob sidecar subnet from Deneb, of which there are more from Electra. From
+/// Fulu the blob sidecar subnets are replaced by the data column sidecar subnets.
+pub fn expected_topic_count(fork: ForkName, spec: &ChainSpec) -> usize {
+    let mut count = global_topic_names(fork).len() + spec.attestation_subnet_count as usize;
+    if fork >= ForkName::Altair {
+        count += SYNC_COMMITTEE_SUBNET_COUNT as usize;
+    }
+    if fork >= ForkName::Fulu {
+        count += DATA_COLUMN_SIDECAR_SUBNET_COUNT as usize;
+    } else if fork >= ForkName::Electra {
+        count += BLOB_SIDECAR_SUBNET_COUNT_ELECTRA as usize;
+    } else if fork >= ForkName::Deneb {
+        count += BLOB_SIDECAR_SUBNET_COUNT as usize;
+    }
+    count
+}
+
+/// Returns the global topics plus the topics for the given attestation and sync committee subnets.
+///
+/// Subnet ids outside of the spec bounds are ignored and the result contains no duplicates.
+pub fn validator_topic_ids(
+    enr_fork_id: EnrForkId,
+    attestation_subnets: &[u64],
+    sync_subnets: &[u64],
+    spec: &ChainSpec,
+) -> Vec<String> {
+    let mut topic_ids = create_topic_ids(enr_fork_id.clone());
+    let attestation_topic_ids = attestation_subnets
+        .iter()
+        .filter(|subnet_id| **subnet_id < spec.attestation_subnet_count)
+        .map(|subnet_id| attestation_subnet_topic_id(*subnet_id, enr_fork_id.clone()));
+    let sync_topic_ids = sync_subnets
+        .iter()
+        .filter(|subnet_id| **subnet_id < SYNC_COMMITTEE_SUBNET_COUNT)
+        .map(|subnet_id| sync_committee_subnet_topic_id(*subnet_id, enr_fork_id.clone()));
+
+    for topic_id in attestation_topic_ids.chain(sync_topic_ids) {
+        if !topic_ids.contains(&topic_id) {
+            topic_ids.push(topic_id);
+        }
+    }
+    topic_ids
+}
+
+/// The topics a node should subscribe to for its validators' duties at `epoch`.
+///
+/// This is the global topics plus the node's `backbone_subnets` and the `duty_subnets` its
+/// validators need for their current attestation duties, as given by
+/// `compute_subnet_for_attestation`. Backbone subscriptions are per node since Deneb, so they
+/// depend only on `node_id`.
+pub fn topics_for_validators(
+    node_id: &NodeId,
+    duty_subnets: &[u64],
+    epoch: Epoch,
+    enr_fork_id: EnrForkId,
+    spec: &ChainSpec,
+) -> Vec<String> {
+    let mut subnets = backbone_subnets(node_id, epoch, spec);
+    subnets.extend_from_slice(duty_subnets);
+    subnets.sort();
+    subnets.dedup();
+    validator_topic_ids(enr_fork_id, &subnets, &[], spec)
+}
+
+/// Picks an encoding from `supported`, preferring ssz_snappy, and returns the hash of the topic.
+///
+/// Returns `None` if no encoding is supported.
+pub fn gossip_topic_to_negotiated_hash(
+    kind: GossipKind,
+    enr_fork_id: EnrForkId,
+    supported: &[GossipEncoding],
+) -> Option<TopicHash> {
+    let encoding = supported
+        .iter()
+        .find(|encoding| **encoding == GossipEncoding::SSZSnappy)
+        .or_else(|| supported.first())?;
+    let topic: Topic = GossipTopic::new(kind, encoding.clone(), enr_fork_id.fork_digest).into();
+    Some(topic.no_hash())
+}
+
+/// Returns the topics of every fork in the schedule, without duplicates.
+pub fn all_historical_topic_ids(
+    genesis_validators_root: Hash256,
+    schedule: &ForkSchedule,
+) -> Vec<String> {
+    let mut topic_ids: Vec<String> = vec![];
+    for enr_fork_id in scheduled_fork_ids(genesis_validators_root, schedule) {
+        for topic_id in create_topic_ids(enr_fork_id) {
+            if !topic_ids.contains(&topic_id) {
+                topic_ids.push(topic_id);
+            }
+        }
+    }
+    topic_ids
+}
+
+/// The topics of the next fork advertised in `current`, for subscribing ahead of the fork.
+///
+/// Returns `None` if no next fork is scheduled.
+pub fn next_fork_topic_ids(
+    current: &EnrForkId,
+    genesis_validators_root: Hash256,
+    spec: &ChainSpec,
+) -> Option<Vec<String>> {
+    if current.next_fork_epoch == spec.far_future_epoch {
+        return None;
+    }
+    let fork_digest =
+        ChainSpec::compute_fork_digest(current.next_fork_version, genesis_validators_root);
+    Some(topic_ids_from_digest(fork_digest))
+}
+
+/// Compares the current subscriptions against the expected ones.
+///
+/// Returns the `(missing, extra)` topics, each in the order of its source list.
+pub fn topic_subscription_drift(
+    current: &[String],
+    expected: &[String],
+) -> (Vec<String>, Vec<String>) {
+    let missing = expected
+        .iter()
+        .filter(|topic_id| !current.contains(topic_id))
+        .cloned()
+        .collect();
+    let extra = current
+        .iter()
+        .filter(|topic_id| !expected.contains(topic_id))
+        .cloned()
+        .collect();
+    (missing, extra)
+}
+
+/// The `(added, removed)` topics going from the `old` topics to the `new` ones.
+pub fn topic_id_diff(old: &[String], new: &[String]) -> (Vec<String>, Vec<String>) {
+    topic_subscription_drift(old, new)
+}
+
+/// The topic changes at each fork that activates after `from` and at or before `to`.
+///
+/// Each entry is the activation epoch with the topics added and removed at that fork.
+pub fn topic_churn_between_epochs(
+    from: Epoch,
+    to: Epoch,
+    genesis_validators_root: Hash256,
+    schedule: &ForkSchedule,
+) -> Vec<(Epoch, Vec<String>, Vec<String>)> {
+    let fork_ids = scheduled_fork_ids(genesis_validators_root, schedule);
+    schedule
+        .forks()
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(_, (_, epoch, _))| *epoch > from && *epoch <= to)
+        .map(|(i, (_, epoch, _))| {
+            let old = create_topic_ids(fork_ids[i - 1].clone());
+            let new = create_topic_ids(fork_ids[i].clone());
+            let (added, removed) = topic_id_diff(&old, &new);
+            (*epoch, added, removed)
+        })
+        .collect()
+}
+
+/// The default `NetworkConfig` subscribed to only the given kinds.
+///
+/// Pass the result to `create_topic_ids_for_config` to get exactly those topics.
+pub fn network_config_with_topics(kinds: Vec<GossipKind>) -> NetworkConfig {
+    let mut network_config = NetworkConfig::default();
+    network_config.topics = kinds;
+    network_config
+}
+
+pub fn parse_gossip_topic(topic: &str) -> Option<ParsedTopic> {
+    let parts: Vec<&str> = topic.split('/').collect();
+    match parts.as_slice() {
+        ["", prefix, fork_digest, name, encoding] if *prefix == TOPIC_PREFIX => {
+            let fork_digest_bytes = hex::decode(fork_digest).ok()?;
+            if fork_digest_bytes.len() != 4 || name.is_empty() || encoding.is_empty() {
+                return None;
+            }
+            let mut fork_digest = [0u8; 4];
+            fork_digest.copy_from_slice(&fork_digest_bytes);
+            Some(ParsedTopic {
+                fork_digest,
+                name: name.to_string(),
+                encoding: encoding.to_string(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Splits a topic name like `beacon_attestation_5` into its subnet prefix and subnet id.
+pub fn subnet_from_topic_name(name: &str) -> Option<(&'static str, u64)> {
+    SUBNET_TOPIC_PREFIXES.iter().find_map(|prefix| {
+        if !name.starts_with(prefix) {
+            return None;
+        }
+        let subnet_id = &name[prefix.len()..];
+        if subnet_id.is_empty() || !subnet_id.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        subnet_id
+            .parse::<u64>()
+            .ok()
+            .map(|subnet_id| (*prefix, subnet_id))
+    })
+}
+
+/// Returns true for attestation, sync committee and blob sidecar subnet topics.
+pub fn is_subnet_topic(topic: &str) -> bool {
+    parse_gossip_topic(topic).map_or(false, |parsed| {
+        subnet_from_topic_name(&parsed.name).is_some()
+    })
+}
+
+/// Returns true if the ENR's `attnets` and the attestation subnet topics describe the same set of
+/// subnets. Topics that are not attestation subnet topics are ignored.
+pub fn attnets_consistent_with_topics<E: EthSpec>(
+    enr: &Enr<CombinedKey>,
+    advertised_topics: &[String],
+) -> bool {
+    let attnets: BTreeSet<u64> = get_attnets_for_spec::<E, _>(enr).into_iter().collect();
+    let topic_subnets: BTreeSet<u64> = advertised_topics
+        .iter()
+        .filter_map(|topic| parse_gossip_topic(topic))
+        .filter_map(|parsed| subnet_from_topic_name(&parsed.name))
+        .filter(|(prefix, _)| *prefix == BEACON_ATTESTATION_PREFIX)
+        .map(|(_, subnet_id)| subnet_id)
+        .collect();
+    attnets == topic_subnets
+}
+
+/// Returns true if the topic uses the `ssz_snappy` encoding. Unparseable topics never match.
+pub fn topic_uses_ssz_snappy(topic: &str) -> bool {
+    parse_gossip_topic(topic).map_or(false, |parsed| {
+        parsed.encoding == SSZ_SNAPPY_ENCODING_POSTFIX
+    })
+}
+
+/// Returns true if the topic is for the fork with `current_digest`. Unparseable topics never match.
+pub fn topic_matches_current_fork(topic: &str, current_digest: [u8; 4]) -> bool {
+    parse_gossip_topic(topic).map_or(false, |parsed| parsed.fork_digest == current_digest)
+}
+
+/// Builds topic ids for a fork with a configurable encoding.
+///
+/// The encoding defaults to `GossipEncoding::default()`.
+#[derive(Clone)]
+pub struct TopicBuilder {
+    encoding: GossipEncoding,
+    enr_fork_id: EnrForkId,
+}
+
+impl TopicBuilder {
+    pub fn new(enr_fork_id: EnrForkId) -> Self {
+        TopicBuilder {
+            encoding: GossipEncoding::default(),
+            enr_fork_id,
+        }
+    }
+
+    pub fn encoding(mut self, encoding: GossipEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn topic(&self, kind: GossipKind) -> String {
+        GossipTopic::new(kind, self.encoding.clone(), self.enr_fork_id.fork_digest).into()
+    }
+
+    /// The topics of the default `NetworkConfig`.
+    pub fn all_default_topics(&self) -> Vec<String> {
+        NetworkConfig::default()
+            .topics
+            .into_iter()
+            .map(|kind| self.topic(kind))
+            .collect()
+    }
+}
+
+/// Computes the 20 byte gossipsub message-id for a received message.
+///
+/// The id is `SHA256(domain + snappy_decompress(data))[:20]`, falling back to the invalid snappy
+/// domain and the raw data if decompression fails. Data whose snappy header claims more than
+/// `GOSSIP_MAX_SIZE` bytes is treated as invalid snappy without decompressing it. From Altair
+/// onwards the topic, prefixed by its length as a little endian `u64`, is hashed in between the
+/// domain and the data.
+pub fn compute_gossip_message_id(topic: &str, data: &[u8], fork: ForkName) -> Vec<u8> {
+    let decompressed = match snap::raw::decompress_len(data) {
+        Ok(len) if len <= GOSSIP_MAX_SIZE => snap::raw::Decoder::new().decompress_vec(data).ok(),
+        _ => None,
+    };
+    let (domain, data) = match decompressed {
+        Some(decompressed) => (MESSAGE_DOMAIN_VALID_SNAPPY, decompressed),
+        None => (MESSAGE_DOMAIN_INVALID_SNAPPY, data.to_vec()),
+    };
+    let mut preimage = domain.to_vec();
+    if fork >= ForkName::Altair {
+        preimage.extend_from_slice(&(topic.len() as u64).to_le_bytes());
+        preimage.extend_from_slice(topic.as_bytes());
+    }
+    preimage.extend_from_slice(&data);
+    let mut message_id = eth2_hashing::hash(&preimage);
+    message_id.truncate(20);
+    message_id
+}
+
+/// The topics both nodes would gossip on.
+///
+/// Nodes on different fork digests share nothing. Otherwise this is the global topics plus the
+/// subnet topics for the attnets and syncnets both nodes advertise. An empty result means the
+/// peer is of no use on gossip.
+pub fn shared_topics(
+    mine: &Enr<CombinedKey>,
+    theirs: &Enr<CombinedKey>,
+    spec: &ChainSpec,
+) -> Vec<String> {
+    let enr_fork_id = match (get_fork_id_from_enr(mine), get_fork_id_from_enr(theirs)) {
+        (Some(x), Some(y)) if x.fork_digest == y.fork_digest => x,
+        _ => return vec![],
+    };
+    let their_attnets = get_attnets_from_enr(theirs);
+    let their_syncnets = get_syncnets_from_enr(theirs);
+    let attnets: Vec<u64> = get_attnets_from_enr(mine)
+        .into_iter()
+        .filter(|subnet_id| their_attnets.contains(subnet_id))
+        .collect();
+    let syncnets: Vec<u64> = get_syncnets_from_enr(mine)
+        .into_iter()
+        .filter(|subnet_id| their_syncnets.contains(subnet_id))
+        .collect();
+    validator_topic_ids(enr_fork_id, &attnets, &syncnets, spec)
+}
+
+/// The default topics for the fork the peer advertises, or `None` if it has no eth2 field.
+pub fn topic_ids_for_peer(enr: &Enr<CombinedKey>) -> Option<Vec<String>> {
+    get_fork_id_from_enr(enr).map(create_topic_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::genesis_fork_id;
+
+    #[test]
+    fn test_is_subnet_topic() {
+        assert!(is_subnet_topic(
+            "/eth2/b5303f2a/beacon_attestation_5/ssz_snappy"
+        ));
+        assert!(is_subnet_topic(
+            "/eth2/b5303f2a/sync_committee_3/ssz_snappy"
+        ));
+        assert!(is_subnet_topic("/eth2/b5303f2a/blob_sidecar_0/ssz_snappy"));
+        assert!(!is_subnet_topic("/eth2/b5303f2a/beacon_block/ssz_snappy"));
+        assert!(!is_subnet_topic(
+            "/eth2/b5303f2a/beacon_aggregate_and_proof/ssz_snappy"
+        ));
+        assert!(!is_subnet_topic(
+            "/eth2/b5303f2a/beacon_attestation_/ssz_snappy"
+        ));
+        assert!(!is_subnet_topic("beacon_attestation_5"));
+    }
+
+    #[test]
+    fn test_compute_gossip_message_id() {
+        let topic = "/eth2/b5303f2a/beacon_block/ssz_snappy";
+        // raw snappy encoding of "hello"
+        let valid_snappy = [0x05, 0x10, 0x68, 0x65, 0x6c, 0x6c, 0x6f];
+        let invalid_snappy = [0xff, 0xff];
+        assert_eq!(
+            hex::encode(compute_gossip_message_id(
+                topic,
+                &valid_snappy,
+                ForkName::Phase0
+            )),
+            "79d62a59d0e47597aeb73cb85ba034c3f67f90e8"
+        );
+        assert_eq!(
+            hex::encode(compute_gossip_message_id(
+                topic,
+                &invalid_snappy,
+                ForkName::Phase0
+            )),
+            "70bf5dd334d1d0f5346e284db74989aabf87c2c4"
+        );
+        assert_eq!(
+            hex::encode(compute_gossip_message_id(
+                topic,
+                &valid_snappy,
+                ForkName::Altair
+            )),
+            "3d7a296829e282226186d1490994d29c05faf37a"
+        );
+        assert_eq!(
+            hex::encode(compute_gossip_message_id(
+                topic,
+                &invalid_snappy,
+                ForkName::Altair
+            )),
+            "58ed8514745de67923bc83292225535418211fe3"
+        );
+    }
+
+    #[test]
+    fn test_compute_gossip_message_id_oversized_snappy() {
+        // headers claiming 2^32 - 1 bytes and one byte more than the gossip max size
+        let oversized: [&[u8]; 2] = [
+            &[0xff, 0xff, 0xff, 0xff, 0x0f, 0x00, 0x61],
+            &[0x81, 0x80, 0x40, 0x00, 0x61],
+        ];
+        for data in oversized.iter() {
+            let mut preimage = MESSAGE_DOMAIN_INVALID_SNAPPY.to_vec();
+            preimage.extend_from_slice(data);
+            assert_eq!(
+                compute_gossip_message_id("", data, ForkName::Phase0),
+                eth2_hashing::hash(&preimage)[..20].to_vec()
+            );
+        }
+    }
+
+    #[test]
+    fn test_slashing_topic_ids() {
+        let fork_id = genesis_fork_id(Hash256::zero(), &ChainSpec::mainnet());
+        let names: Vec<String> = slashing_topic_ids(fork_id)
+            .iter()
+            .map(|topic| parse_gossip_topic(topic).unwrap().name)
+            .collect();
+        assert_eq!(
+            names,
+            vec!["proposer_slashing", "attester_slashing", "voluntary_exit"]
+        );
+    }
+
+    #[test]
+    fn test_gossip_kind_code_round_trip() {
+        let mut kinds = vec![
+            GossipKind::BeaconBlock,
+            GossipKind::BeaconAggregateAndProof,
+            GossipKind::VoluntaryExit,
+            GossipKind::ProposerSlashing,
+            GossipKind::AttesterSlashing,
+        ];
+        kinds.extend((0..64).map(|i| GossipKind::Attestation(SubnetId::new(i))));
+        for kind in kinds {
+            assert_eq!(gossip_kind_from_code(gossip_kind_code(&kind)), Some(kind));
+        }
+        assert_eq!(gossip_kind_code(&GossipKind::BeaconBlock), 0);
+        assert_eq!(
+            gossip_kind_code(&GossipKind::Attestation(SubnetId::new(5))),
+            0x105
+        );
+        assert_eq!(gossip_kind_from_code(5), None);
+        assert_eq!(gossip_kind_from_code(0x200), None);
+
+        // ids past the 8 bit range fold onto lower codes, and fail validation
+        let out_of_range = GossipKind::Attestation(SubnetId::new(261));
+        assert_eq!(gossip_kind_code(&out_of_range), 0x105);
+        assert!(validate_gossip_kind(&out_of_range, &ChainSpec::mainnet()).is_err());
+    }
+
+    fn three_fork_schedule() -> ForkSchedule {
+        ForkSchedule::new(vec![
+            (ForkName::Phase0, Epoch::new(0), [0, 0, 0, 0]),
+            (ForkName::Altair, Epoch::new(10), [1, 0, 0, 0]),
+            (ForkName::Bellatrix, Epoch::new(20), [2, 0, 0, 0]),
+        ])
+    }
+
+    #[test]
+    fn test_all_historical_topic_ids() {
+        let root = Hash256::zero();
+        let schedule = three_fork_schedule();
+        let topic_ids = all_historical_topic_ids(root, &schedule);
+        let fork_ids = scheduled_fork_ids(root, &schedule);
+        let per_fork = create_topic_ids(fork_ids[0].clone()).len();
+        assert_eq!(topic_ids.len(), 3 * per_fork);
+        for enr_fork_id in fork_ids {
+            for topic_id in create_topic_ids(enr_fork_id) {
+                assert!(topic_ids.contains(&topic_id));
+            }
+        }
+        // a repeated fork version adds no topics
+        let mut forks = schedule.forks().to_vec();
+        forks.push((ForkName::Capella, Epoch::new(30), [2, 0, 0, 0]));
+        assert_eq!(
+            all_historical_topic_ids(root, &ForkSchedule::new(forks)).len(),
+            3 * per_fork
+        );
+    }
+
+    #[test]
+    fn test_topic_churn_between_epochs() {
+        let root = Hash256::zero();
+        let schedule = three_fork_schedule();
+        let fork_ids = scheduled_fork_ids(root, &schedule);
+        let churn = topic_churn_between_epochs(Epoch::new(0), Epoch::new(100), root, &schedule);
+        assert_eq!(churn.len(), 2);
+        assert_eq!(churn[0].0, Epoch::new(10));
+        assert_eq!(churn[1].0, Epoch::new(20));
+        let (added, removed) = (&churn[1].1, &churn[1].2);
+        assert_eq!(added, &create_topic_ids(fork_ids[2].clone()));
+        assert_eq!(removed, &create_topic_ids(fork_ids[1].clone()));
+
+        // `from` is exclusive and `to` inclusive
+        let churn = topic_churn_between_epochs(Epoch::new(10), Epoch::new(20), root, &schedule);
+        assert_eq!(churn.len(), 1);
+        assert_eq!(churn[0].0, Epoch::new(20));
+        assert!(
+            topic_churn_between_epochs(Epoch::new(20), Epoch::new(100), root, &schedule).is_empty()
+        );
+    }
+
+    #[test]
+    fn test_topics_for_validators() {
+        let spec = ChainSpec::mainnet();
+        let enr_fork_id = genesis_fork_id(Hash256::zero(), &spec);
+        let mut raw = [0u8; 32];
+        for (i, byte) in raw.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        let node_id = NodeId::new(&raw);
+        let subnet_topic = |subnet_id| attestation_subnet_topic_id(subnet_id, enr_fork_id.clone());
+
+        let topic_ids =
+            topics_for_validators(&node_id, &[], Epoch::new(0), enr_fork_id.clone(), &spec);
+        assert_eq!(
+            topic_ids.len(),
+            create_topic_ids(enr_fork_id.clone()).len() + 2
+        );
+        assert!(topic_ids.contains(&subnet_topic(49)));
+        assert!(topic_ids.contains(&subnet_topic(50)));
+
+        // duty subnets are added once, alongside the backbone
+        let topic_ids = topics_for_validators(
+            &node_id,
+            &[3, 50, 3],
+            Epoch::new(0),
+            enr_fork_id.clone(),
+            &spec,
+        );
+        assert_eq!(topic_ids.len(), create_topic_ids(enr_fork_id).len() + 3);
+        assert!(topic_ids.contains(&subnet_topic(3)));
+    }
+}